@@ -4,9 +4,86 @@ use serde::Deserialize;
 pub struct Config {
   pub database_url: String,
   pub redis_url: String,
-  pub jwt_secret: String,
+  /// How access tokens are signed: `hs256`, `rs256` or `eddsa`.
+  #[serde(default)]
+  pub jwt_algorithm: JwtAlgorithm,
+  /// Shared secret, only used with `hs256`.
+  pub jwt_secret: Option<String>,
+  /// PEM file with the private key tokens are signed with. Only the auth
+  /// service needs it.
+  pub jwt_private_key: Option<String>,
+  /// Key ID of `jwt_private_key`; it must be among `jwt_public_keys`.
+  pub jwt_key_id: Option<String>,
+  /// Public keys tokens are accepted from, as comma-separated `kid=path`
+  /// pairs. Keeping retired keys listed lets their tokens run out.
+  #[serde(default)]
+  pub jwt_public_keys: Vec<String>,
   pub port_auth: u16,
   pub port_room_management: u16,
+  /// Where outgoing mail goes: `smtp`, `file` or `memory`.
+  #[serde(default)]
+  pub mail_transport: MailTransport,
+  #[serde(default = "default_smtp_host")]
+  pub smtp_host: String,
+  #[serde(default = "default_smtp_port")]
+  pub smtp_port: u16,
+  pub smtp_username: Option<String>,
+  pub smtp_password: Option<String>,
+  /// Upgrade SMTP connections with STARTTLS. Off for local mail catchers.
+  #[serde(default)]
+  pub smtp_starttls: bool,
+  #[serde(default = "default_mail_from")]
+  pub mail_from: String,
+  /// Directory the `file` transport writes `.eml` files to.
+  #[serde(default = "default_mail_dir")]
+  pub mail_dir: String,
+  /// Refuse staff actions to admins who did not log in with a second factor.
+  #[serde(default)]
+  pub require_admin_2fa: bool,
+  /// Key invitation links are signed with.
+  pub invitation_secret: Option<String>,
+  /// Public URL of the frontend, used for links in emails.
+  #[serde(default = "default_app_url")]
+  pub app_url: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransport {
+  #[default]
+  Smtp,
+  File,
+  Memory,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JwtAlgorithm {
+  /// Shared secret; meant for local development.
+  #[default]
+  Hs256,
+  Rs256,
+  EdDsa,
+}
+
+fn default_smtp_host() -> String {
+  "localhost".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+  1025
+}
+
+fn default_mail_from() -> String {
+  "DormMatch <no-reply@dormmatch.local>".to_string()
+}
+
+fn default_mail_dir() -> String {
+  "mail".to_string()
+}
+
+fn default_app_url() -> String {
+  "http://localhost".to_string()
 }
 
 impl Config {
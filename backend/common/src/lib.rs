@@ -3,3 +3,5 @@ pub mod repositories;
 pub mod types;
 pub mod utils;
 pub mod config;
+pub mod mail;
+pub mod middleware;
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use crate::config::env::{Config, MailTransport};
+use lettre::{
+  message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+  AsyncTransport, Message, Tokio1Executor,
+};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct Email {
+  pub to: String,
+  pub subject: String,
+  pub body: String,
+}
+
+/// Outgoing mail. Handlers take it as `web::Data<dyn Mailer>`, so tests can
+/// swap the SMTP transport for a `MemoryMailer`.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+  async fn send(&self, email: &Email) -> Result<(), String>;
+}
+
+fn build_message(from: &Mailbox, email: &Email) -> Result<Message, String> {
+  let to: Mailbox = email
+    .to
+    .parse()
+    .map_err(|e| format!("Invalid recipient {}: {}", email.to, e))?;
+  Message::builder()
+    .from(from.clone())
+    .to(to)
+    .subject(&email.subject)
+    .body(email.body.clone())
+    .map_err(|e| format!("Cannot build message: {}", e))
+}
+
+pub struct SmtpMailer {
+  transport: AsyncSmtpTransport<Tokio1Executor>,
+  from: Mailbox,
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+  async fn send(&self, email: &Email) -> Result<(), String> {
+    let message = build_message(&self.from, email)?;
+    self
+      .transport
+      .send(message)
+      .await
+      .map(|_| ())
+      .map_err(|e| format!("SMTP error: {}", e))
+  }
+}
+
+/// Writes every message to its own `.eml` file.
+pub struct FileMailer {
+  dir: PathBuf,
+  from: Mailbox,
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+  async fn send(&self, email: &Email) -> Result<(), String> {
+    let message = build_message(&self.from, email)?;
+    let path = self.dir.join(format!("{}.eml", Uuid::new_v4()));
+    tokio::fs::create_dir_all(&self.dir)
+      .await
+      .map_err(|e| format!("Cannot create {}: {}", self.dir.display(), e))?;
+    tokio::fs::write(&path, message.formatted())
+      .await
+      .map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+  }
+}
+
+/// Keeps sent mail in memory for inspection.
+#[derive(Default)]
+pub struct MemoryMailer {
+  sent: Mutex<Vec<Email>>,
+}
+
+impl MemoryMailer {
+  pub fn sent(&self) -> Vec<Email> {
+    self.sent.lock().unwrap().clone()
+  }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+  async fn send(&self, email: &Email) -> Result<(), String> {
+    self.sent.lock().unwrap().push(email.clone());
+    Ok(())
+  }
+}
+
+/// The mailer selected by `MAIL_TRANSPORT`.
+pub fn from_config(config: &Config) -> Arc<dyn Mailer> {
+  let from: Mailbox = config.mail_from.parse().expect("Invalid MAIL_FROM");
+  match config.mail_transport {
+    MailTransport::Smtp => {
+      let mut builder = if config.smtp_starttls {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+          .expect("Invalid SMTP_HOST")
+      } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+      };
+      builder = builder.port(config.smtp_port);
+      if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+      }
+      Arc::new(SmtpMailer {
+        transport: builder.build(),
+        from,
+      })
+    }
+    MailTransport::File => Arc::new(FileMailer {
+      dir: PathBuf::from(&config.mail_dir),
+      from,
+    }),
+    MailTransport::Memory => Arc::new(MemoryMailer::default()),
+  }
+}
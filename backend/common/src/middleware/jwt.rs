@@ -0,0 +1,42 @@
+use actix_web::{dev::ServiceRequest, Error, HttpMessage};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use redis::Client;
+
+use crate::{
+  utils::{
+    jwt::{verify_jwt, JwtKeys},
+    revocation::is_revoked,
+  },
+};
+
+/// Bearer-token validator for `HttpAuthentication::bearer`. Rejects invalid
+/// and revoked tokens and makes the token's `Claims` available to handlers
+/// through `web::ReqData<Claims>`. Needs `JwtKeys` and a Redis `Client` in
+/// the app data.
+pub async fn jwt_middleware(
+  req: ServiceRequest,
+  credentials: BearerAuth,
+) -> Result<ServiceRequest, (Error, ServiceRequest)> {
+  let keys = req
+    .app_data::<actix_web::web::Data<JwtKeys>>()
+    .expect("JWT keys not found in app data");
+  let redis = req
+    .app_data::<actix_web::web::Data<Client>>()
+    .expect("Redis client not found in app data");
+
+  let claims = match verify_jwt(credentials.token(), keys) {
+    Ok(claims) => claims,
+    Err(_) => return Err((actix_web::error::ErrorUnauthorized("Invalid token"), req)),
+  };
+  match is_revoked(redis, &claims).await {
+    Ok(false) => {
+      req.extensions_mut().insert(claims);
+      Ok(req)
+    }
+    Ok(true) => Err((actix_web::error::ErrorUnauthorized("Token has been revoked"), req)),
+    Err(_) => Err((
+      actix_web::error::ErrorServiceUnavailable("Session store unavailable"),
+      req,
+    )),
+  }
+}
@@ -0,0 +1,111 @@
+use std::future::{ready, Future, Ready};
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+use actix_web::{dev::Payload, web, FromRequest, HttpMessage, HttpRequest};
+use sqlx::PgPool;
+
+use crate::config::env::Config;
+use crate::models::role_assignment::{Permission, RoleAssignment, Scope};
+use crate::repositories::role_assignment::RoleAssignmentRepository;
+use crate::utils::jwt::Claims;
+
+/// A set of roles allowed to call a route.
+pub trait RequiredRole {
+  const ROLES: &'static [&'static str];
+  /// Shown in the 403 response.
+  const DESCRIPTION: &'static str;
+}
+
+pub struct Admin;
+
+impl RequiredRole for Admin {
+  const ROLES: &'static [&'static str] = &["admin"];
+  const DESCRIPTION: &'static str = "Admin role required";
+}
+
+/// Extractor that only lets callers with one of `R::ROLES` through.
+///
+/// Relies on `jwt_middleware` having stored the caller's `Claims`; answers
+/// 401 when there are none and 403 when the role does not match.
+pub struct RoleGuard<R: RequiredRole> {
+  pub claims: Claims,
+  _role: PhantomData<R>,
+}
+
+impl<R: RequiredRole> FromRequest for RoleGuard<R> {
+  type Error = actix_web::Error;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+    let claims = req.extensions().get::<Claims>().cloned();
+    ready(match claims {
+      None => Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
+      Some(claims) if R::ROLES.contains(&claims.role.as_str()) => Ok(RoleGuard {
+        claims,
+        _role: PhantomData,
+      }),
+      Some(_) => Err(actix_web::error::ErrorForbidden(R::DESCRIPTION)),
+    })
+  }
+}
+
+/// Extractor for staff routes: an admin token plus the caller's role
+/// assignments. Assignments are loaded on every request, so a revoked role
+/// stops working without waiting for the token to expire. When
+/// `require_admin_2fa` is on, tokens from a password-only login get 403.
+pub struct StaffAccess {
+  pub claims: Claims,
+  pub assignments: Vec<RoleAssignment>,
+}
+
+impl StaffAccess {
+  /// Whether any of the caller's roles grants `permission` in `scope`.
+  pub fn can(&self, permission: Permission, scope: Scope) -> bool {
+    self
+      .assignments
+      .iter()
+      .any(|assignment| assignment.allows(permission, scope))
+  }
+
+  /// Same as `can`, but answers 403 when the permission is missing.
+  pub fn require(&self, permission: Permission, scope: Scope) -> Result<(), actix_web::Error> {
+    if self.can(permission, scope) {
+      Ok(())
+    } else {
+      Err(actix_web::error::ErrorForbidden(format!(
+        "Permission {:?} is not granted for this scope",
+        permission
+      )))
+    }
+  }
+}
+
+impl FromRequest for StaffAccess {
+  type Error = actix_web::Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+  fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+    let guard = RoleGuard::<Admin>::from_request(req, payload).into_inner();
+    let pool = req.app_data::<web::Data<PgPool>>().cloned();
+    let require_mfa = req
+      .app_data::<web::Data<Config>>()
+      .is_some_and(|config| config.require_admin_2fa);
+    Box::pin(async move {
+      let claims = guard?.claims;
+      if require_mfa && !claims.mfa {
+        return Err(actix_web::error::ErrorForbidden(
+          "Two-factor authentication is required for staff actions",
+        ));
+      }
+      let user_id = claims
+        .user_id()
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+      let pool = pool.ok_or_else(|| actix_web::error::ErrorInternalServerError("Database pool is not configured"))?;
+      let assignments = RoleAssignmentRepository::find_by_user_id(&pool, &user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Role lookup failed: {}", e)))?;
+      Ok(StaffAccess { claims, assignments })
+    })
+  }
+}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A request for an administrator account. No user exists for the applicant
+/// until a super admin approves it.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct AdminApplication {
+  pub id: uuid::Uuid,
+  pub email: String,
+  /// University or office the applicant works for.
+  pub organisation: String,
+  pub position: String,
+  pub status: AdminApplicationStatus,
+  pub submitted_at: DateTime<Utc>,
+  pub reviewed_at: Option<DateTime<Utc>>,
+  pub reviewed_by: Option<uuid::Uuid>,
+  pub review_note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "admin_application_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AdminApplicationStatus {
+  Pending,
+  Approved,
+  Rejected,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A committed allocation run. The applications it produced reference it
+/// through `Application::allocation_run_id`.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct AllocationRun {
+  pub id: uuid::Uuid,
+  pub mode: String,
+  pub matching_settings_version: Option<i32>,
+  /// Quality metrics of the plan that was committed.
+  pub summary: serde_json::Value,
+  pub created_at: DateTime<Utc>,
+}
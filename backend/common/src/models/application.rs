@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
 use sqlx::FromRow;
 use utoipa::ToSchema;
 
@@ -8,7 +9,69 @@ pub struct Application {
   pub id: uuid::Uuid,
   pub user_id: uuid::Uuid,
   pub room_id: uuid::Uuid,
-  pub status: String,
+  pub status: ApplicationStatus,
   pub comment: Option<String>,
   pub created_at: DateTime<Utc>,
+  /// Matching settings version that produced an automatic assignment.
+  pub matching_settings_version: Option<i32>,
+  /// Allocation run that created this application, if any.
+  pub allocation_run_id: Option<uuid::Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "application_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationStatus {
+  Pending,
+  Approved,
+  Rejected,
+  Withdrawn,
+  CheckedIn,
+  CheckedOut,
+}
+
+impl ApplicationStatus {
+  pub const ALL: [ApplicationStatus; 6] = [
+    ApplicationStatus::Pending,
+    ApplicationStatus::Approved,
+    ApplicationStatus::Rejected,
+    ApplicationStatus::Withdrawn,
+    ApplicationStatus::CheckedIn,
+    ApplicationStatus::CheckedOut,
+  ];
+
+  /// Statuses an application may move to from this one. Rejected, withdrawn
+  /// and checked-out applications are final.
+  pub fn transitions(self) -> &'static [ApplicationStatus] {
+    match self {
+      ApplicationStatus::Pending => &[
+        ApplicationStatus::Approved,
+        ApplicationStatus::Rejected,
+        ApplicationStatus::Withdrawn,
+      ],
+      ApplicationStatus::Approved => &[ApplicationStatus::CheckedIn, ApplicationStatus::Withdrawn],
+      ApplicationStatus::CheckedIn => &[ApplicationStatus::CheckedOut],
+      ApplicationStatus::Rejected | ApplicationStatus::Withdrawn | ApplicationStatus::CheckedOut => {
+        &[]
+      }
+    }
+  }
+
+  pub fn can_transition_to(self, next: ApplicationStatus) -> bool {
+    self.transitions().contains(&next)
+  }
+
+  /// Statuses an application may reach this one from.
+  pub fn sources(self) -> Vec<ApplicationStatus> {
+    Self::ALL
+      .into_iter()
+      .filter(|status| status.can_transition_to(self))
+      .collect()
+  }
+}
+
+impl PgHasArrayType for ApplicationStatus {
+  fn array_type_info() -> PgTypeInfo {
+    PgTypeInfo::with_name("_application_status")
+  }
 }
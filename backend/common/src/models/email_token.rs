@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "email_token_purpose", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EmailTokenPurpose {
+  ConfirmEmail,
+  ResetPassword,
+}
+
+/// A single-use token sent by email. Only the SHA-256 of the token is
+/// stored, so a database leak does not hand out working links.
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct EmailToken {
+  pub token_hash: String,
+  pub user_id: uuid::Uuid,
+  pub purpose: EmailTokenPurpose,
+  pub created_at: DateTime<Utc>,
+  pub expires_at: DateTime<Utc>,
+  pub used_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use crate::models::{role_assignment::StaffRole, user::UserRole};
+
+/// An invitation to register with a preset role, sent as a signed link.
+/// Invited users skip manual verification.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct Invitation {
+  pub id: uuid::Uuid,
+  pub email: String,
+  pub role: UserRole,
+  /// Staff role granted on registration; set exactly for admin invitations.
+  pub staff_role: Option<StaffRole>,
+  pub building: Option<String>,
+  /// Faculty of an invited student, or of a faculty coordinator.
+  pub faculty: Option<String>,
+  pub invited_by: Option<uuid::Uuid>,
+  pub created_at: DateTime<Utc>,
+  pub expires_at: DateTime<Utc>,
+  pub last_sent_at: DateTime<Utc>,
+  pub redeemed_at: Option<DateTime<Utc>>,
+  /// Account registered through the invitation.
+  pub redeemed_by: Option<uuid::Uuid>,
+  pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Invitation {
+  /// Neither used nor revoked, and not expired by `now`.
+  pub fn is_outstanding(&self, now: DateTime<Utc>) -> bool {
+    self.redeemed_at.is_none() && self.revoked_at.is_none() && self.expires_at > now
+  }
+}
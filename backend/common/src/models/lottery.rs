@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A lottery allocation. While it is open only the seed's hash is public;
+/// the draw reveals the seed and sets `drawn_at` and `allocation_run_id`.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct Lottery {
+  pub id: uuid::Uuid,
+  /// Hex-encoded SHA-256 of the seed, published when the lottery is
+  /// announced.
+  pub seed_hash: String,
+  /// The seed itself, revealed once the lottery has been drawn.
+  pub seed: Option<String>,
+  pub announced_at: DateTime<Utc>,
+  pub drawn_at: Option<DateTime<Utc>>,
+  pub allocation_run_id: Option<uuid::Uuid>,
+}
+
+/// The applicant drawn at `position` (0 first) for a room.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct LotteryEntry {
+  pub lottery_id: uuid::Uuid,
+  pub room_id: uuid::Uuid,
+  pub position: i32,
+  pub user_id: uuid::Uuid,
+  pub admitted: bool,
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// One version of the criterion weights used to score roommate
+/// compatibility. Exactly one version is active at a time; versions that
+/// have ever been active are kept unchanged so past assignments stay
+/// explainable.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct MatchingSettings {
+  pub version: i32,
+  pub wake_hours_weight: f64,
+  pub mbti_weight: f64,
+  pub hobbies_weight: f64,
+  pub age_weight: f64,
+  pub faculty_weight: f64,
+  pub course_weight: f64,
+  /// Rooms scoring below this percentage are not offered or assigned.
+  pub min_score: f64,
+  pub is_active: bool,
+  pub activated_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+}
@@ -2,3 +2,14 @@ pub mod user;
 pub mod profile;
 pub mod room;
 pub mod application;
+pub mod matching_settings;
+pub mod allocation_run;
+pub mod preference;
+pub mod lottery;
+pub mod residency;
+pub mod role_assignment;
+pub mod roster;
+pub mod email_token;
+pub mod admin_application;
+pub mod invitation;
+pub mod two_factor;
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A room a student ranked; rank 1 is the most wanted.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct RoomPreference {
+  pub user_id: uuid::Uuid,
+  pub room_id: uuid::Uuid,
+  pub rank: i32,
+  pub created_at: DateTime<Utc>,
+}
+
+/// Social-benefit category that gives a student priority in allocation runs.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct SocialBenefit {
+  pub user_id: uuid::Uuid,
+  pub category: String,
+  pub granted_at: DateTime<Utc>,
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A student living in a room. The residency is current while
+/// `moved_out_at` is empty.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct Residency {
+  pub id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub room_id: uuid::Uuid,
+  /// Approved application the residency came from.
+  pub application_id: Option<uuid::Uuid>,
+  pub moved_in_at: DateTime<Utc>,
+  pub moved_out_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Staff role held by an admin account. Building managers and faculty
+/// coordinators only act within the building or faculty of their assignment.
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "staff_role", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum StaffRole {
+  SuperAdmin,
+  BuildingManager,
+  FacultyCoordinator,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+  /// Create rooms and change their status.
+  ManageRooms,
+  /// Approve, reject and otherwise move applications.
+  ReviewApplications,
+  /// See who lives in a room and move residents out.
+  ManageResidencies,
+  /// Confirm or reject student accounts.
+  VerifyStudents,
+  /// Run allocations, lotteries and manage social benefits.
+  RunAllocations,
+  /// Edit matching settings.
+  ManageSettings,
+  /// Grant and revoke staff roles.
+  ManageStaff,
+  /// Lift login lockouts caused by repeated failed attempts.
+  UnlockAccounts,
+}
+
+impl StaffRole {
+  /// Each role carries exactly the scope it is limited by.
+  pub fn check_scope(self, building: Option<&str>, faculty: Option<&str>) -> Result<(), String> {
+    let (building, faculty) = (building.is_some(), faculty.is_some());
+    match self {
+      StaffRole::SuperAdmin if building || faculty => {
+        Err("A super admin is not limited to a building or faculty".to_string())
+      }
+      StaffRole::BuildingManager if !building || faculty => {
+        Err("A building manager needs a building and no faculty".to_string())
+      }
+      StaffRole::FacultyCoordinator if building || !faculty => {
+        Err("A faculty coordinator needs a faculty and no building".to_string())
+      }
+      _ => Ok(()),
+    }
+  }
+
+  pub fn permissions(self) -> &'static [Permission] {
+    match self {
+      StaffRole::SuperAdmin => &[
+        Permission::ManageRooms,
+        Permission::ReviewApplications,
+        Permission::ManageResidencies,
+        Permission::VerifyStudents,
+        Permission::RunAllocations,
+        Permission::ManageSettings,
+        Permission::ManageStaff,
+        Permission::UnlockAccounts,
+      ],
+      StaffRole::BuildingManager => &[
+        Permission::ManageRooms,
+        Permission::ReviewApplications,
+        Permission::ManageResidencies,
+      ],
+      StaffRole::FacultyCoordinator => &[Permission::VerifyStudents],
+    }
+  }
+}
+
+/// What an action touches. An empty field means the action is not tied to
+/// a particular building or faculty, which only unscoped roles may perform.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Scope<'a> {
+  pub building: Option<&'a str>,
+  pub faculty: Option<&'a str>,
+}
+
+impl<'a> Scope<'a> {
+  pub fn global() -> Self {
+    Scope::default()
+  }
+
+  pub fn building(building: Option<&'a str>) -> Self {
+    Scope {
+      building,
+      faculty: None,
+    }
+  }
+
+  pub fn faculty(faculty: Option<&'a str>) -> Self {
+    Scope {
+      building: None,
+      faculty,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct RoleAssignment {
+  pub id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub role: StaffRole,
+  /// Building the role is limited to, if any.
+  pub building: Option<String>,
+  /// Faculty the role is limited to, if any.
+  pub faculty: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+impl RoleAssignment {
+  /// Whether this assignment grants `permission` for an action in `scope`.
+  /// Every limit set on the assignment must match the scope exactly.
+  pub fn allows(&self, permission: Permission, scope: Scope) -> bool {
+    let within = |limit: &Option<String>, target: Option<&str>| {
+      limit.as_deref().is_none_or(|limit| target == Some(limit))
+    };
+    self.role.permissions().contains(&permission)
+      && within(&self.building, scope.building)
+      && within(&self.faculty, scope.faculty)
+  }
+}
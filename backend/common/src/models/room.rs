@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
 use sqlx::FromRow;
 use utoipa::ToSchema;
 
@@ -13,5 +14,54 @@ pub struct Room {
   pub faculty_restriction: Option<String>,
   pub course_restriction: Option<i32>,
   pub sex_restriction: String,
-  pub status: String,
+  /// Building the room belongs to; staff roles can be scoped to it.
+  pub building: Option<String>,
+  pub status: RoomStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "room_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RoomStatus {
+  Available,
+  Reserved,
+  Occupied,
+  Maintenance,
+}
+
+impl RoomStatus {
+  pub const ALL: [RoomStatus; 4] = [
+    RoomStatus::Available,
+    RoomStatus::Reserved,
+    RoomStatus::Occupied,
+    RoomStatus::Maintenance,
+  ];
+
+  /// Statuses a room may move to from this one.
+  pub fn transitions(self) -> &'static [RoomStatus] {
+    match self {
+      RoomStatus::Available => &[RoomStatus::Reserved, RoomStatus::Occupied, RoomStatus::Maintenance],
+      RoomStatus::Reserved => &[RoomStatus::Available, RoomStatus::Occupied],
+      RoomStatus::Occupied => &[RoomStatus::Available],
+      RoomStatus::Maintenance => &[RoomStatus::Available],
+    }
+  }
+
+  pub fn can_transition_to(self, next: RoomStatus) -> bool {
+    self.transitions().contains(&next)
+  }
+
+  /// Statuses a room may reach this one from.
+  pub fn sources(self) -> Vec<RoomStatus> {
+    Self::ALL
+      .into_iter()
+      .filter(|status| status.can_transition_to(self))
+      .collect()
+  }
+}
+
+impl PgHasArrayType for RoomStatus {
+  fn array_type_info() -> PgTypeInfo {
+    PgTypeInfo::with_name("_room_status")
+  }
 }
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// One upload of the university's student roster. Every import is a full
+/// snapshot: students missing from it are dropped from the roster.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct RosterImport {
+  pub id: uuid::Uuid,
+  pub imported_by: Option<uuid::Uuid>,
+  pub imported_at: DateTime<Utc>,
+  pub accepted_rows: i32,
+  pub rejected_rows: i32,
+}
+
+/// An enrolled student as listed by the university.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct RosterEntry {
+  /// University student ID.
+  pub student_id: String,
+  pub email: String,
+  pub faculty: String,
+  pub course: i32,
+  /// Latest import that listed the student.
+  pub import_id: uuid::Uuid,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "roster_change_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RosterChangeKind {
+  Added,
+  Removed,
+  /// Faculty or course differ from the previous import.
+  Changed,
+}
+
+impl PgHasArrayType for RosterChangeKind {
+  fn array_type_info() -> PgTypeInfo {
+    PgTypeInfo::with_name("_roster_change_kind")
+  }
+}
+
+/// How one student's entry changed with an import.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct RosterChange {
+  pub import_id: uuid::Uuid,
+  pub student_id: String,
+  pub kind: RosterChangeKind,
+  pub email: String,
+  pub old_faculty: Option<String>,
+  pub new_faculty: Option<String>,
+  pub old_course: Option<i32>,
+  pub new_course: Option<i32>,
+  /// Account registered with the email when the import ran.
+  pub user_id: Option<uuid::Uuid>,
+}
+
+/// A user who dropped off the roster. Flags with a residency wait in the
+/// review queue until an admin resolves them.
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone)]
+pub struct RosterFlag {
+  pub user_id: uuid::Uuid,
+  pub import_id: uuid::Uuid,
+  pub flagged_at: DateTime<Utc>,
+  /// Residency that was active when the user was flagged.
+  pub residency_id: Option<uuid::Uuid>,
+  pub resolved_at: Option<DateTime<Utc>>,
+  pub resolved_by: Option<uuid::Uuid>,
+  pub resolution: Option<String>,
+}
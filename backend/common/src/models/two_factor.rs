@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A user's TOTP secret. Enrollment is finished once the first code from
+/// the authenticator app has been accepted.
+#[derive(Serialize, Deserialize, FromRow, Clone)]
+pub struct TotpCredential {
+  pub user_id: uuid::Uuid,
+  #[serde(skip_serializing)]
+  pub secret: Vec<u8>,
+  pub created_at: DateTime<Utc>,
+  pub confirmed_at: Option<DateTime<Utc>>,
+  /// Latest time step a code was accepted for; codes cannot be reused.
+  pub last_used_step: Option<i64>,
+}
@@ -12,10 +12,13 @@ pub struct User {
   pub status: String,
   pub created_at: DateTime<Utc>,
   pub updated_at: DateTime<Utc>,
+  /// Unset until the user follows the link in the confirmation email.
+  pub email_confirmed_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "user_role", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum UserRole {
   Student,
   Admin,
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::admin_application::{AdminApplication, AdminApplicationStatus};
+
+pub struct AdminApplicationRepository;
+
+impl AdminApplicationRepository {
+  pub async fn create(pool: &PgPool, application: &AdminApplication) -> Result<AdminApplication, sqlx::Error> {
+    sqlx::query_as!(
+      AdminApplication,
+      r#"
+            INSERT INTO admin_applications (id, email, organisation, position, status, submitted_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, email, organisation, position, status AS "status: AdminApplicationStatus",
+                      submitted_at, reviewed_at, reviewed_by, review_note
+            "#,
+      application.id,
+      application.email,
+      application.organisation,
+      application.position,
+      application.status as AdminApplicationStatus,
+      application.submitted_at
+    )
+    .fetch_one(pool)
+    .await
+  }
+
+  pub async fn find_by_id<'e, E: PgExecutor<'e>>(
+    executor: E,
+    id: &Uuid,
+  ) -> Result<Option<AdminApplication>, sqlx::Error> {
+    sqlx::query_as!(
+      AdminApplication,
+      r#"
+            SELECT id, email, organisation, position, status AS "status: AdminApplicationStatus",
+                   submitted_at, reviewed_at, reviewed_by, review_note
+            FROM admin_applications
+            WHERE id = $1
+            "#,
+      id
+    )
+    .fetch_optional(executor)
+    .await
+  }
+
+  /// Applications nobody has decided on yet, oldest first.
+  pub async fn find_pending(pool: &PgPool) -> Result<Vec<AdminApplication>, sqlx::Error> {
+    sqlx::query_as!(
+      AdminApplication,
+      r#"
+            SELECT id, email, organisation, position, status AS "status: AdminApplicationStatus",
+                   submitted_at, reviewed_at, reviewed_by, review_note
+            FROM admin_applications
+            WHERE status = 'pending'
+            ORDER BY submitted_at
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  /// Records the decision unless one was made already. Returns the reviewed
+  /// application, or `None` when it does not exist or is no longer pending.
+  pub async fn review<'e, E: PgExecutor<'e>>(
+    executor: E,
+    id: &Uuid,
+    status: AdminApplicationStatus,
+    reviewed_by: &Uuid,
+    note: Option<&str>,
+    reviewed_at: DateTime<Utc>,
+  ) -> Result<Option<AdminApplication>, sqlx::Error> {
+    sqlx::query_as!(
+      AdminApplication,
+      r#"
+            UPDATE admin_applications
+            SET status = $2, reviewed_at = $4, reviewed_by = $3, review_note = $5
+            WHERE id = $1 AND status = 'pending'
+            RETURNING id, email, organisation, position, status AS "status: AdminApplicationStatus",
+                      submitted_at, reviewed_at, reviewed_by, review_note
+            "#,
+      id,
+      status as AdminApplicationStatus,
+      reviewed_by,
+      reviewed_at,
+      note
+    )
+    .fetch_optional(executor)
+    .await
+  }
+}
@@ -0,0 +1,62 @@
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::{allocation_run::AllocationRun, application::Application};
+use crate::repositories::application::ApplicationRepository;
+
+pub struct AllocationRunRepository;
+
+impl AllocationRunRepository {
+  /// Stores the run together with the applications it produced, all or
+  /// nothing.
+  pub async fn create_with_applications(
+    pool: &PgPool,
+    run: &AllocationRun,
+    applications: &[Application],
+  ) -> Result<(AllocationRun, Vec<Application>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let run = Self::insert(&mut *tx, run).await?;
+
+    let mut created = Vec::with_capacity(applications.len());
+    for application in applications {
+      created.push(ApplicationRepository::insert(&mut *tx, application).await?);
+    }
+
+    tx.commit().await?;
+    Ok((run, created))
+  }
+
+  /// Inserts a run without its applications; usable inside a transaction.
+  pub async fn insert<'e, E: PgExecutor<'e>>(
+    executor: E,
+    run: &AllocationRun,
+  ) -> Result<AllocationRun, sqlx::Error> {
+    sqlx::query_as!(
+      AllocationRun,
+      r#"
+            INSERT INTO allocation_runs (id, mode, matching_settings_version, summary, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, mode, matching_settings_version, summary, created_at
+            "#,
+      run.id,
+      &run.mode,
+      run.matching_settings_version,
+      &run.summary,
+      run.created_at
+    )
+    .fetch_one(executor)
+    .await
+  }
+
+  pub async fn find_all(pool: &PgPool) -> Result<Vec<AllocationRun>, sqlx::Error> {
+    sqlx::query_as!(
+      AllocationRun,
+      r#"
+            SELECT id, mode, matching_settings_version, summary, created_at
+            FROM allocation_runs ORDER BY created_at DESC
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+}
@@ -1,25 +1,35 @@
-use sqlx::PgPool;
-use crate::models::application::Application;
+use sqlx::{PgExecutor, PgPool};
+use crate::models::application::{Application, ApplicationStatus};
 
 pub struct ApplicationRepository;
 
 impl ApplicationRepository {
     pub async fn create(pool: &PgPool, app: &Application) -> Result<Application, sqlx::Error> {
+        Self::insert(pool, app).await
+    }
+
+    /// Same as `create`, but usable inside a transaction.
+    pub async fn insert<'e, E: PgExecutor<'e>>(
+        executor: E,
+        app: &Application,
+    ) -> Result<Application, sqlx::Error> {
         sqlx::query_as!(
             Application,
             r#"
-            INSERT INTO applications (id, user_id, room_id, status, comment, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, user_id, room_id, status, comment, created_at
+            INSERT INTO applications (id, user_id, room_id, status, comment, created_at, matching_settings_version, allocation_run_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, user_id, room_id, status AS "status: ApplicationStatus", comment, created_at, matching_settings_version, allocation_run_id
             "#,
             app.id,
             app.user_id,
             app.room_id,
-            &app.status,
+            app.status as ApplicationStatus,
             app.comment,
-            app.created_at
+            app.created_at,
+            app.matching_settings_version,
+            app.allocation_run_id
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await
     }
 
@@ -30,7 +40,7 @@ impl ApplicationRepository {
         sqlx::query_as!(
             Application,
             r#"
-            SELECT id, user_id, room_id, status, comment, created_at
+            SELECT id, user_id, room_id, status AS "status: ApplicationStatus", comment, created_at, matching_settings_version, allocation_run_id
             FROM applications WHERE user_id = $1
             "#,
             user_id
@@ -39,25 +49,83 @@ impl ApplicationRepository {
         .await
     }
 
-    pub async fn update_status(
+    /// Applications for the given rooms in one of `statuses`, oldest first.
+    pub async fn find_by_room_ids(
+        pool: &PgPool,
+        room_ids: &[uuid::Uuid],
+        statuses: &[ApplicationStatus],
+    ) -> Result<Vec<Application>, sqlx::Error> {
+        sqlx::query_as!(
+            Application,
+            r#"
+            SELECT id, user_id, room_id, status AS "status: ApplicationStatus", comment, created_at, matching_settings_version, allocation_run_id
+            FROM applications WHERE room_id = ANY($1) AND status = ANY($2)
+            ORDER BY created_at
+            "#,
+            room_ids,
+            statuses as &[ApplicationStatus]
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_id(
         pool: &PgPool,
         id: &uuid::Uuid,
-        status: &str,
+    ) -> Result<Option<Application>, sqlx::Error> {
+        sqlx::query_as!(
+            Application,
+            r#"
+            SELECT id, user_id, room_id, status AS "status: ApplicationStatus", comment, created_at, matching_settings_version, allocation_run_id
+            FROM applications WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Same as `find_by_id`, but locks the row until the transaction ends.
+    pub async fn find_by_id_for_update<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: &uuid::Uuid,
+    ) -> Result<Option<Application>, sqlx::Error> {
+        sqlx::query_as!(
+            Application,
+            r#"
+            SELECT id, user_id, room_id, status AS "status: ApplicationStatus", comment, created_at, matching_settings_version, allocation_run_id
+            FROM applications WHERE id = $1
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Moves the application to `status` if its current status allows it.
+    /// Returns `None` when the application does not exist or the transition
+    /// is not allowed.
+    pub async fn update_status<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: &uuid::Uuid,
+        status: ApplicationStatus,
         comment: Option<String>,
-    ) -> Result<Application, sqlx::Error> {
+    ) -> Result<Option<Application>, sqlx::Error> {
         sqlx::query_as!(
             Application,
             r#"
             UPDATE applications
             SET status = $1, comment = $2
-            WHERE id = $3
-            RETURNING id, user_id, room_id, status, comment, created_at
+            WHERE id = $3 AND status = ANY($4)
+            RETURNING id, user_id, room_id, status AS "status: ApplicationStatus", comment, created_at, matching_settings_version, allocation_run_id
             "#,
-            status,
+            status as ApplicationStatus,
             comment,
-            id
+            id,
+            &status.sources() as &[ApplicationStatus]
         )
-        .fetch_one(pool)
+        .fetch_optional(executor)
         .await
     }
 }
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::email_token::{EmailToken, EmailTokenPurpose};
+
+pub struct EmailTokenRepository;
+
+impl EmailTokenRepository {
+  pub async fn create(pool: &PgPool, token: &EmailToken) -> Result<EmailToken, sqlx::Error> {
+    sqlx::query_as!(
+      EmailToken,
+      r#"
+            INSERT INTO email_tokens (token_hash, user_id, purpose, created_at, expires_at, used_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING token_hash, user_id, purpose AS "purpose: EmailTokenPurpose", created_at, expires_at, used_at
+            "#,
+      token.token_hash,
+      token.user_id,
+      token.purpose as EmailTokenPurpose,
+      token.created_at,
+      token.expires_at,
+      token.used_at
+    )
+    .fetch_one(pool)
+    .await
+  }
+
+  /// Marks a token as used if it exists, has this purpose, is unused and has
+  /// not expired by `now`. Returns `None` otherwise.
+  pub async fn consume<'e, E: PgExecutor<'e>>(
+    executor: E,
+    token_hash: &str,
+    purpose: EmailTokenPurpose,
+    now: DateTime<Utc>,
+  ) -> Result<Option<EmailToken>, sqlx::Error> {
+    sqlx::query_as!(
+      EmailToken,
+      r#"
+            UPDATE email_tokens SET used_at = $3
+            WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > $3
+            RETURNING token_hash, user_id, purpose AS "purpose: EmailTokenPurpose", created_at, expires_at, used_at
+            "#,
+      token_hash,
+      purpose as EmailTokenPurpose,
+      now
+    )
+    .fetch_optional(executor)
+    .await
+  }
+
+  /// Retires the user's outstanding tokens for `purpose`, so only the most
+  /// recently sent link works.
+  pub async fn invalidate<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: &Uuid,
+    purpose: EmailTokenPurpose,
+    now: DateTime<Utc>,
+  ) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+      r#"
+            UPDATE email_tokens SET used_at = $3
+            WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
+            "#,
+      user_id,
+      purpose as EmailTokenPurpose,
+      now
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+  }
+
+  /// When the newest token for `purpose` was issued to the user.
+  pub async fn last_issued_at(
+    pool: &PgPool,
+    user_id: &Uuid,
+    purpose: EmailTokenPurpose,
+  ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    sqlx::query_scalar!(
+      r#"
+            SELECT MAX(created_at) FROM email_tokens
+            WHERE user_id = $1 AND purpose = $2
+            "#,
+      user_id,
+      purpose as EmailTokenPurpose
+    )
+    .fetch_one(pool)
+    .await
+  }
+}
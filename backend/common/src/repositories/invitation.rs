@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::{invitation::Invitation, role_assignment::StaffRole, user::UserRole};
+
+pub struct InvitationRepository;
+
+impl InvitationRepository {
+  pub async fn create<'e, E: PgExecutor<'e>>(
+    executor: E,
+    invitation: &Invitation,
+  ) -> Result<Invitation, sqlx::Error> {
+    sqlx::query_as!(
+      Invitation,
+      r#"
+            INSERT INTO invitations (id, email, role, staff_role, building, faculty, invited_by,
+                                     created_at, expires_at, last_sent_at, redeemed_at, redeemed_by, revoked_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            RETURNING id, email, role AS "role: UserRole", staff_role AS "staff_role: StaffRole", building, faculty,
+                      invited_by, created_at, expires_at, last_sent_at, redeemed_at, redeemed_by, revoked_at
+            "#,
+      invitation.id,
+      invitation.email,
+      invitation.role as UserRole,
+      invitation.staff_role as Option<StaffRole>,
+      invitation.building,
+      invitation.faculty,
+      invitation.invited_by,
+      invitation.created_at,
+      invitation.expires_at,
+      invitation.last_sent_at,
+      invitation.redeemed_at,
+      invitation.redeemed_by,
+      invitation.revoked_at
+    )
+    .fetch_one(executor)
+    .await
+  }
+
+  pub async fn find_by_id<'e, E: PgExecutor<'e>>(
+    executor: E,
+    id: &Uuid,
+  ) -> Result<Option<Invitation>, sqlx::Error> {
+    sqlx::query_as!(
+      Invitation,
+      r#"
+            SELECT id, email, role AS "role: UserRole", staff_role AS "staff_role: StaffRole", building, faculty,
+                   invited_by, created_at, expires_at, last_sent_at, redeemed_at, redeemed_by, revoked_at
+            FROM invitations WHERE id = $1
+            "#,
+      id
+    )
+    .fetch_optional(executor)
+    .await
+  }
+
+  /// Invitations that can still be redeemed at `now`, oldest first.
+  pub async fn find_outstanding(pool: &PgPool, now: DateTime<Utc>) -> Result<Vec<Invitation>, sqlx::Error> {
+    sqlx::query_as!(
+      Invitation,
+      r#"
+            SELECT id, email, role AS "role: UserRole", staff_role AS "staff_role: StaffRole", building, faculty,
+                   invited_by, created_at, expires_at, last_sent_at, redeemed_at, redeemed_by, revoked_at
+            FROM invitations
+            WHERE redeemed_at IS NULL AND revoked_at IS NULL AND expires_at > $1
+            ORDER BY created_at
+            "#,
+      now
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  pub async fn mark_sent(pool: &PgPool, id: &Uuid, sent_at: DateTime<Utc>) -> Result<Invitation, sqlx::Error> {
+    sqlx::query_as!(
+      Invitation,
+      r#"
+            UPDATE invitations SET last_sent_at = $2
+            WHERE id = $1
+            RETURNING id, email, role AS "role: UserRole", staff_role AS "staff_role: StaffRole", building, faculty,
+                      invited_by, created_at, expires_at, last_sent_at, redeemed_at, redeemed_by, revoked_at
+            "#,
+      id,
+      sent_at
+    )
+    .fetch_one(pool)
+    .await
+  }
+
+  /// Revokes the invitation unless it was already used or revoked.
+  pub async fn revoke(pool: &PgPool, id: &Uuid, revoked_at: DateTime<Utc>) -> Result<Option<Invitation>, sqlx::Error> {
+    sqlx::query_as!(
+      Invitation,
+      r#"
+            UPDATE invitations SET revoked_at = $2
+            WHERE id = $1 AND redeemed_at IS NULL AND revoked_at IS NULL
+            RETURNING id, email, role AS "role: UserRole", staff_role AS "staff_role: StaffRole", building, faculty,
+                      invited_by, created_at, expires_at, last_sent_at, redeemed_at, redeemed_by, revoked_at
+            "#,
+      id,
+      revoked_at
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  /// Marks the invitation used by `user_id` if it is still outstanding at
+  /// `now`. Returns whether it was.
+  pub async fn redeem<'e, E: PgExecutor<'e>>(
+    executor: E,
+    id: &Uuid,
+    user_id: &Uuid,
+    now: DateTime<Utc>,
+  ) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+      r#"
+            UPDATE invitations SET redeemed_at = $3, redeemed_by = $2
+            WHERE id = $1 AND redeemed_at IS NULL AND revoked_at IS NULL AND expires_at > $3
+            "#,
+      id,
+      user_id,
+      now
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() == 1)
+  }
+}
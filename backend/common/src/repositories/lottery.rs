@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+  allocation_run::AllocationRun,
+  application::Application,
+  lottery::{Lottery, LotteryEntry},
+};
+use crate::repositories::{allocation_run::AllocationRunRepository, application::ApplicationRepository};
+
+pub struct LotteryRepository;
+
+impl LotteryRepository {
+  /// Announces a lottery and freezes the current room rankings as its
+  /// applicants. Fails with a unique violation while another lottery is
+  /// open.
+  pub async fn create(
+    pool: &PgPool,
+    id: &Uuid,
+    seed: &str,
+    seed_hash: &str,
+  ) -> Result<Lottery, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let lottery = sqlx::query_as!(
+      Lottery,
+      r#"
+            INSERT INTO lotteries (id, seed, seed_hash, announced_at)
+            VALUES ($1, $2, $3, NOW())
+            RETURNING id, seed_hash, NULL::VARCHAR AS "seed?", announced_at, drawn_at, allocation_run_id
+            "#,
+      id,
+      seed,
+      seed_hash
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+      r#"
+            INSERT INTO lottery_preferences (lottery_id, user_id, room_id, rank)
+            SELECT $1, user_id, room_id, rank FROM room_preferences
+            "#,
+      id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(lottery)
+  }
+
+  /// The lottery announced but not drawn yet, if any.
+  pub async fn find_open(pool: &PgPool) -> Result<Option<Lottery>, sqlx::Error> {
+    sqlx::query_as!(
+      Lottery,
+      r#"
+            SELECT id, seed_hash, NULL::VARCHAR AS "seed?", announced_at, drawn_at, allocation_run_id
+            FROM lotteries WHERE drawn_at IS NULL
+            "#
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  /// The seed, including that of a lottery not drawn yet. Only the draw
+  /// may use it.
+  pub async fn find_seed(pool: &PgPool, id: &Uuid) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar!(r#"SELECT seed FROM lotteries WHERE id = $1"#, id)
+      .fetch_optional(pool)
+      .await
+  }
+
+  /// Rankings frozen when the lottery was announced, best room first.
+  pub async fn find_preferences(
+    pool: &PgPool,
+    lottery_id: &Uuid,
+  ) -> Result<HashMap<Uuid, Vec<Uuid>>, sqlx::Error> {
+    let rows = sqlx::query!(
+      r#"
+            SELECT user_id, room_id FROM lottery_preferences
+            WHERE lottery_id = $1 ORDER BY user_id, rank
+            "#,
+      lottery_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut preferences: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for row in rows {
+      preferences.entry(row.user_id).or_default().push(row.room_id);
+    }
+    Ok(preferences)
+  }
+
+  pub async fn find_by_id(pool: &PgPool, id: &Uuid) -> Result<Option<Lottery>, sqlx::Error> {
+    sqlx::query_as!(
+      Lottery,
+      r#"
+            SELECT id, seed_hash, CASE WHEN drawn_at IS NULL THEN NULL ELSE seed END AS "seed?",
+                   announced_at, drawn_at, allocation_run_id
+            FROM lotteries WHERE id = $1
+            "#,
+      id
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  pub async fn find_all(pool: &PgPool) -> Result<Vec<Lottery>, sqlx::Error> {
+    sqlx::query_as!(
+      Lottery,
+      r#"
+            SELECT id, seed_hash, CASE WHEN drawn_at IS NULL THEN NULL ELSE seed END AS "seed?",
+                   announced_at, drawn_at, allocation_run_id
+            FROM lotteries ORDER BY announced_at DESC
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  pub async fn find_entries(
+    pool: &PgPool,
+    lottery_id: &Uuid,
+  ) -> Result<Vec<LotteryEntry>, sqlx::Error> {
+    sqlx::query_as!(
+      LotteryEntry,
+      r#"
+            SELECT lottery_id, room_id, position, user_id, admitted
+            FROM lottery_draws WHERE lottery_id = $1 ORDER BY room_id, position
+            "#,
+      lottery_id
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  /// Stores the draw results, the allocation run and its applications, and
+  /// marks the lottery as drawn, all or nothing. Returns `None` if the
+  /// lottery has already been drawn.
+  pub async fn record_draw(
+    pool: &PgPool,
+    lottery_id: &Uuid,
+    entries: &[LotteryEntry],
+    run: &AllocationRun,
+    applications: &[Application],
+  ) -> Result<Option<Lottery>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    AllocationRunRepository::insert(&mut *tx, run).await?;
+    for application in applications {
+      ApplicationRepository::insert(&mut *tx, application).await?;
+    }
+
+    let lottery = sqlx::query_as!(
+      Lottery,
+      r#"
+            UPDATE lotteries SET drawn_at = $2, allocation_run_id = $3
+            WHERE id = $1 AND drawn_at IS NULL
+            RETURNING id, seed_hash, seed AS "seed?", announced_at, drawn_at, allocation_run_id
+            "#,
+      lottery_id,
+      run.created_at,
+      run.id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(lottery) = lottery else {
+      return Ok(None);
+    };
+
+    for entry in entries {
+      sqlx::query!(
+        r#"
+            INSERT INTO lottery_draws (lottery_id, room_id, position, user_id, admitted)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        entry.lottery_id,
+        entry.room_id,
+        entry.position,
+        entry.user_id,
+        entry.admitted
+      )
+      .execute(&mut *tx)
+      .await?;
+    }
+
+    tx.commit().await?;
+    Ok(Some(lottery))
+  }
+}
@@ -0,0 +1,164 @@
+use sqlx::PgPool;
+
+use crate::models::matching_settings::MatchingSettings;
+
+/// Weights and threshold of a settings version, as submitted by an admin.
+pub struct MatchingWeights {
+  pub wake_hours_weight: f64,
+  pub mbti_weight: f64,
+  pub hobbies_weight: f64,
+  pub age_weight: f64,
+  pub faculty_weight: f64,
+  pub course_weight: f64,
+  pub min_score: f64,
+}
+
+pub struct MatchingSettingsRepository;
+
+impl MatchingSettingsRepository {
+  pub async fn find_active(pool: &PgPool) -> Result<Option<MatchingSettings>, sqlx::Error> {
+    sqlx::query_as!(
+      MatchingSettings,
+      r#"
+            SELECT version, wake_hours_weight, mbti_weight, hobbies_weight, age_weight,
+                   faculty_weight, course_weight, min_score, is_active, activated_at, created_at
+            FROM matching_settings WHERE is_active
+            "#
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  pub async fn find_all(pool: &PgPool) -> Result<Vec<MatchingSettings>, sqlx::Error> {
+    sqlx::query_as!(
+      MatchingSettings,
+      r#"
+            SELECT version, wake_hours_weight, mbti_weight, hobbies_weight, age_weight,
+                   faculty_weight, course_weight, min_score, is_active, activated_at, created_at
+            FROM matching_settings ORDER BY version DESC
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  pub async fn find_by_version(
+    pool: &PgPool,
+    version: i32,
+  ) -> Result<Option<MatchingSettings>, sqlx::Error> {
+    sqlx::query_as!(
+      MatchingSettings,
+      r#"
+            SELECT version, wake_hours_weight, mbti_weight, hobbies_weight, age_weight,
+                   faculty_weight, course_weight, min_score, is_active, activated_at, created_at
+            FROM matching_settings WHERE version = $1
+            "#,
+      version
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  pub async fn create(
+    pool: &PgPool,
+    weights: &MatchingWeights,
+  ) -> Result<MatchingSettings, sqlx::Error> {
+    sqlx::query_as!(
+      MatchingSettings,
+      r#"
+            INSERT INTO matching_settings (
+                wake_hours_weight, mbti_weight, hobbies_weight, age_weight,
+                faculty_weight, course_weight, min_score, is_active, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, FALSE, NOW())
+            RETURNING version, wake_hours_weight, mbti_weight, hobbies_weight, age_weight,
+                      faculty_weight, course_weight, min_score, is_active, activated_at, created_at
+            "#,
+      weights.wake_hours_weight,
+      weights.mbti_weight,
+      weights.hobbies_weight,
+      weights.age_weight,
+      weights.faculty_weight,
+      weights.course_weight,
+      weights.min_score
+    )
+    .fetch_one(pool)
+    .await
+  }
+
+  /// Updates a version that has never been activated. Returns `None` when
+  /// the version does not exist or has already been in use.
+  pub async fn update(
+    pool: &PgPool,
+    version: i32,
+    weights: &MatchingWeights,
+  ) -> Result<Option<MatchingSettings>, sqlx::Error> {
+    sqlx::query_as!(
+      MatchingSettings,
+      r#"
+            UPDATE matching_settings
+            SET wake_hours_weight = $2, mbti_weight = $3, hobbies_weight = $4, age_weight = $5,
+                faculty_weight = $6, course_weight = $7, min_score = $8
+            WHERE version = $1 AND activated_at IS NULL
+            RETURNING version, wake_hours_weight, mbti_weight, hobbies_weight, age_weight,
+                      faculty_weight, course_weight, min_score, is_active, activated_at, created_at
+            "#,
+      version,
+      weights.wake_hours_weight,
+      weights.mbti_weight,
+      weights.hobbies_weight,
+      weights.age_weight,
+      weights.faculty_weight,
+      weights.course_weight,
+      weights.min_score
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  /// Deletes a version that has never been activated. Returns whether a
+  /// row was removed.
+  pub async fn delete(pool: &PgPool, version: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+      r#"DELETE FROM matching_settings WHERE version = $1 AND activated_at IS NULL"#,
+      version
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+  }
+
+  /// Makes `version` the only active settings version.
+  pub async fn activate(
+    pool: &PgPool,
+    version: i32,
+  ) -> Result<Option<MatchingSettings>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+      r#"UPDATE matching_settings SET is_active = FALSE WHERE is_active AND version <> $1"#,
+      version
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let settings = sqlx::query_as!(
+      MatchingSettings,
+      r#"
+            UPDATE matching_settings
+            SET is_active = TRUE, activated_at = COALESCE(activated_at, NOW())
+            WHERE version = $1
+            RETURNING version, wake_hours_weight, mbti_weight, hobbies_weight, age_weight,
+                      faculty_weight, course_weight, min_score, is_active, activated_at, created_at
+            "#,
+      version
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if settings.is_some() {
+      tx.commit().await?;
+    }
+    Ok(settings)
+  }
+}
@@ -0,0 +1,110 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::preference::{RoomPreference, SocialBenefit};
+
+pub struct RoomPreferenceRepository;
+
+impl RoomPreferenceRepository {
+  /// Replaces the student's ranking with `room_ids`, best first.
+  pub async fn replace(
+    pool: &PgPool,
+    user_id: &Uuid,
+    room_ids: &[Uuid],
+  ) -> Result<Vec<RoomPreference>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(r#"DELETE FROM room_preferences WHERE user_id = $1"#, user_id)
+      .execute(&mut *tx)
+      .await?;
+
+    let mut preferences = Vec::with_capacity(room_ids.len());
+    for (index, room_id) in room_ids.iter().enumerate() {
+      let preference = sqlx::query_as!(
+        RoomPreference,
+        r#"
+            INSERT INTO room_preferences (user_id, room_id, rank, created_at)
+            VALUES ($1, $2, $3, NOW())
+            RETURNING user_id, room_id, rank, created_at
+            "#,
+        user_id,
+        room_id,
+        index as i32 + 1
+      )
+      .fetch_one(&mut *tx)
+      .await?;
+      preferences.push(preference);
+    }
+
+    tx.commit().await?;
+    Ok(preferences)
+  }
+
+  pub async fn find_by_user_id(
+    pool: &PgPool,
+    user_id: &Uuid,
+  ) -> Result<Vec<RoomPreference>, sqlx::Error> {
+    sqlx::query_as!(
+      RoomPreference,
+      r#"
+            SELECT user_id, room_id, rank, created_at
+            FROM room_preferences WHERE user_id = $1 ORDER BY rank
+            "#,
+      user_id
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  pub async fn find_all(pool: &PgPool) -> Result<Vec<RoomPreference>, sqlx::Error> {
+    sqlx::query_as!(
+      RoomPreference,
+      r#"
+            SELECT user_id, room_id, rank, created_at
+            FROM room_preferences ORDER BY user_id, rank
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+}
+
+pub struct SocialBenefitRepository;
+
+impl SocialBenefitRepository {
+  pub async fn upsert(
+    pool: &PgPool,
+    user_id: &Uuid,
+    category: &str,
+  ) -> Result<SocialBenefit, sqlx::Error> {
+    sqlx::query_as!(
+      SocialBenefit,
+      r#"
+            INSERT INTO social_benefits (user_id, category, granted_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (user_id) DO UPDATE SET category = EXCLUDED.category, granted_at = NOW()
+            RETURNING user_id, category, granted_at
+            "#,
+      user_id,
+      category
+    )
+    .fetch_one(pool)
+    .await
+  }
+
+  pub async fn delete(pool: &PgPool, user_id: &Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM social_benefits WHERE user_id = $1"#, user_id)
+      .execute(pool)
+      .await?;
+    Ok(result.rows_affected() > 0)
+  }
+
+  pub async fn find_all(pool: &PgPool) -> Result<Vec<SocialBenefit>, sqlx::Error> {
+    sqlx::query_as!(
+      SocialBenefit,
+      r#"SELECT user_id, category, granted_at FROM social_benefits"#
+    )
+    .fetch_all(pool)
+    .await
+  }
+}
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use serde_json::json;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use uuid::Uuid;
 
 use crate::models::profile::{Sex, StudentProfile};
@@ -8,9 +10,10 @@ use crate::types::types::{MbtiType, WakeType};
 
 #[async_trait]
 pub trait StudentProfileRepository {
-  async fn create(
+  #[allow(clippy::too_many_arguments)]
+  async fn create<'e, E: PgExecutor<'e>>(
     &self,
-    pool: &PgPool,
+    executor: E,
     user_id: &Uuid,
     faculty: &str,
     course: i32,
@@ -27,8 +30,8 @@ pub trait StudentProfileRepository {
     user_id: &Uuid,
   ) -> Result<Option<StudentProfile>, sqlx::Error>;
 
-  async fn find_by_user_id(
-    pool: &PgPool,
+  async fn find_by_user_id<'e, E: PgExecutor<'e>>(
+    executor: E,
     user_id: &uuid::Uuid,
   ) -> Result<Option<StudentProfile>, sqlx::Error> {
     sqlx::query_as!(
@@ -49,10 +52,90 @@ pub trait StudentProfileRepository {
             "#,
       user_id
     )
-    .fetch_optional(pool)
+    .fetch_optional(executor)
+    .await
+  }
+
+  /// Profiles of verified students who neither live in a room nor wait on a
+  /// pending application, i.e. everyone a batch allocation should place.
+  async fn find_unhoused_verified(pool: &PgPool) -> Result<Vec<StudentProfile>, sqlx::Error> {
+    sqlx::query_as!(
+      StudentProfile,
+      r#"
+            SELECT
+                p.user_id,
+                p.faculty,
+                p.course,
+                p.gender AS "gender: _",
+                p.age,
+                p.wake_hours AS "wake_hours: _",
+                p.hobbies,
+                p.mbti AS "mbti: _",
+                p.updated_at
+            FROM student_profiles p
+            JOIN users u ON u.id = p.user_id
+            WHERE u.role = 'student' AND u.status = 'verified'
+            AND NOT EXISTS (
+                SELECT 1 FROM residencies r
+                WHERE r.user_id = p.user_id AND r.moved_out_at IS NULL
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM applications a
+                WHERE a.user_id = p.user_id AND a.status IN ('pending', 'approved')
+            )
+            ORDER BY u.created_at
+            "#
+    )
+    .fetch_all(pool)
     .await
   }
 
+  /// Profiles of the students currently living in each of the given rooms,
+  /// keyed by room id, according to their current residencies.
+  async fn find_occupants_by_room_ids(
+    pool: &PgPool,
+    room_ids: &[Uuid],
+  ) -> Result<HashMap<Uuid, Vec<StudentProfile>>, sqlx::Error> {
+    let rows = sqlx::query!(
+      r#"
+            SELECT
+                r.room_id,
+                p.user_id,
+                p.faculty,
+                p.course,
+                p.gender AS "gender: String",
+                p.age,
+                p.wake_hours AS "wake_hours: String",
+                p.hobbies,
+                p.mbti AS "mbti: String",
+                p.updated_at
+            FROM residencies r
+            JOIN student_profiles p ON p.user_id = r.user_id
+            WHERE r.room_id = ANY($1) AND r.moved_out_at IS NULL
+            "#,
+      room_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut occupants: HashMap<Uuid, Vec<StudentProfile>> = HashMap::new();
+    for row in rows {
+      occupants.entry(row.room_id).or_default().push(StudentProfile {
+        user_id: row.user_id,
+        faculty: row.faculty,
+        course: row.course,
+        gender: row.gender,
+        age: row.age,
+        wake_hours: row.wake_hours,
+        hobbies: row.hobbies,
+        mbti: row.mbti,
+        updated_at: row.updated_at,
+      });
+    }
+    Ok(occupants)
+  }
+
+  #[allow(clippy::too_many_arguments)]
   async fn update(
     &self,
     pool: &PgPool,
@@ -71,9 +154,9 @@ pub struct PostgresStudentProfileRepository;
 
 #[async_trait]
 impl StudentProfileRepository for PostgresStudentProfileRepository {
-  async fn create(
+  async fn create<'e, E: PgExecutor<'e>>(
     &self,
-    pool: &PgPool,
+    executor: E,
     user_id: &Uuid,
     faculty: &str,
     course: i32,
@@ -98,7 +181,7 @@ impl StudentProfileRepository for PostgresStudentProfileRepository {
         .bind(wake_hours)
         .bind(json!(hobbies))
         .bind(mbti)
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await
   }
 
@@ -113,6 +196,7 @@ impl StudentProfileRepository for PostgresStudentProfileRepository {
       .await
   }
 
+  #[allow(clippy::too_many_arguments)]
   async fn update(
     &self,
     pool: &PgPool,
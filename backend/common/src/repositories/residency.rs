@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::residency::Residency;
+
+pub struct ResidencyRepository;
+
+impl ResidencyRepository {
+  pub async fn create<'e, E: PgExecutor<'e>>(
+    executor: E,
+    residency: &Residency,
+  ) -> Result<Residency, sqlx::Error> {
+    sqlx::query_as!(
+      Residency,
+      r#"
+            INSERT INTO residencies (id, user_id, room_id, application_id, moved_in_at, moved_out_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, user_id, room_id, application_id, moved_in_at, moved_out_at
+            "#,
+      residency.id,
+      residency.user_id,
+      residency.room_id,
+      residency.application_id,
+      residency.moved_in_at,
+      residency.moved_out_at
+    )
+    .fetch_one(executor)
+    .await
+  }
+
+  pub async fn find_active_by_user_id<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: &Uuid,
+  ) -> Result<Option<Residency>, sqlx::Error> {
+    sqlx::query_as!(
+      Residency,
+      r#"
+            SELECT id, user_id, room_id, application_id, moved_in_at, moved_out_at
+            FROM residencies WHERE user_id = $1 AND moved_out_at IS NULL
+            "#,
+      user_id
+    )
+    .fetch_optional(executor)
+    .await
+  }
+
+  pub async fn find_active_by_room_id(
+    pool: &PgPool,
+    room_id: &Uuid,
+  ) -> Result<Vec<Residency>, sqlx::Error> {
+    sqlx::query_as!(
+      Residency,
+      r#"
+            SELECT id, user_id, room_id, application_id, moved_in_at, moved_out_at
+            FROM residencies WHERE room_id = $1 AND moved_out_at IS NULL
+            ORDER BY moved_in_at
+            "#,
+      room_id
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  /// The current residency that came from approving `application_id`.
+  pub async fn find_active_by_application_id<'e, E: PgExecutor<'e>>(
+    executor: E,
+    application_id: &Uuid,
+  ) -> Result<Option<Residency>, sqlx::Error> {
+    sqlx::query_as!(
+      Residency,
+      r#"
+            SELECT id, user_id, room_id, application_id, moved_in_at, moved_out_at
+            FROM residencies WHERE application_id = $1 AND moved_out_at IS NULL
+            "#,
+      application_id
+    )
+    .fetch_optional(executor)
+    .await
+  }
+
+  /// Looks up a residency and locks it until the transaction ends.
+  pub async fn find_by_id_for_update<'e, E: PgExecutor<'e>>(
+    executor: E,
+    id: &Uuid,
+  ) -> Result<Option<Residency>, sqlx::Error> {
+    sqlx::query_as!(
+      Residency,
+      r#"
+            SELECT id, user_id, room_id, application_id, moved_in_at, moved_out_at
+            FROM residencies WHERE id = $1
+            FOR UPDATE
+            "#,
+      id
+    )
+    .fetch_optional(executor)
+    .await
+  }
+
+  /// Ends a current residency. Returns `None` if it has already ended.
+  pub async fn move_out<'e, E: PgExecutor<'e>>(
+    executor: E,
+    id: &Uuid,
+    moved_out_at: DateTime<Utc>,
+  ) -> Result<Option<Residency>, sqlx::Error> {
+    sqlx::query_as!(
+      Residency,
+      r#"
+            UPDATE residencies SET moved_out_at = $2
+            WHERE id = $1 AND moved_out_at IS NULL
+            RETURNING id, user_id, room_id, application_id, moved_in_at, moved_out_at
+            "#,
+      id,
+      moved_out_at
+    )
+    .fetch_optional(executor)
+    .await
+  }
+}
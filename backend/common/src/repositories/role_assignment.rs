@@ -0,0 +1,60 @@
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::role_assignment::{RoleAssignment, StaffRole};
+
+pub struct RoleAssignmentRepository;
+
+impl RoleAssignmentRepository {
+  pub async fn create<'e, E: PgExecutor<'e>>(
+    executor: E,
+    assignment: &RoleAssignment,
+  ) -> Result<RoleAssignment, sqlx::Error> {
+    sqlx::query_as!(
+      RoleAssignment,
+      r#"
+            INSERT INTO role_assignments (id, user_id, role, building, faculty, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, user_id, role AS "role: StaffRole", building, faculty, created_at
+            "#,
+      assignment.id,
+      assignment.user_id,
+      assignment.role as StaffRole,
+      assignment.building,
+      assignment.faculty,
+      assignment.created_at
+    )
+    .fetch_one(executor)
+    .await
+  }
+
+  pub async fn find_by_user_id(pool: &PgPool, user_id: &Uuid) -> Result<Vec<RoleAssignment>, sqlx::Error> {
+    sqlx::query_as!(
+      RoleAssignment,
+      r#"
+            SELECT id, user_id, role AS "role: StaffRole", building, faculty, created_at
+            FROM role_assignments WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+      user_id
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  /// Removes one of the user's assignments. Returns `None` if the user has
+  /// no assignment with this id.
+  pub async fn delete(pool: &PgPool, user_id: &Uuid, id: &Uuid) -> Result<Option<RoleAssignment>, sqlx::Error> {
+    sqlx::query_as!(
+      RoleAssignment,
+      r#"
+            DELETE FROM role_assignments WHERE id = $1 AND user_id = $2
+            RETURNING id, user_id, role AS "role: StaffRole", building, faculty, created_at
+            "#,
+      id,
+      user_id
+    )
+    .fetch_optional(pool)
+    .await
+  }
+}
@@ -1,5 +1,5 @@
-use crate::models::room::Room;
-use sqlx::PgPool;
+use crate::models::room::{Room, RoomStatus};
+use sqlx::{PgExecutor, PgPool};
 
 pub struct RoomRepository;
 
@@ -8,9 +8,9 @@ impl RoomRepository {
         sqlx::query_as!(
             Room,
             r#"
-            INSERT INTO rooms (id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, status)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-            RETURNING id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, status
+            INSERT INTO rooms (id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, status, building)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, building, status AS "status: RoomStatus"
             "#,
             room.id,
             room.number,
@@ -21,7 +21,8 @@ impl RoomRepository {
             room.faculty_restriction,
             room.course_restriction,
             &room.sex_restriction,
-            &room.status,
+            room.status as RoomStatus,
+            room.building,
         )
         .fetch_one(pool)
         .await
@@ -31,7 +32,7 @@ impl RoomRepository {
         sqlx::query_as!(
             Room,
             r#"
-            SELECT id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, status
+            SELECT id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, building, status AS "status: RoomStatus"
             FROM rooms WHERE id = $1
             "#,
             id
@@ -40,6 +41,57 @@ impl RoomRepository {
         .await
     }
 
+    pub async fn find_all(pool: &PgPool) -> Result<Vec<Room>, sqlx::Error> {
+        sqlx::query_as!(
+            Room,
+            r#"
+            SELECT id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, building, status AS "status: RoomStatus"
+            FROM rooms ORDER BY number
+            "#
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Same as `find_by_id`, but locks the row until the transaction ends.
+    pub async fn find_by_id_for_update<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: &uuid::Uuid,
+    ) -> Result<Option<Room>, sqlx::Error> {
+        sqlx::query_as!(
+            Room,
+            r#"
+            SELECT id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, building, status AS "status: RoomStatus"
+            FROM rooms WHERE id = $1
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    pub async fn update_occupancy<'e, E: PgExecutor<'e>>(
+        executor: E,
+        id: &uuid::Uuid,
+        current_occupants: i32,
+        status: RoomStatus,
+    ) -> Result<Room, sqlx::Error> {
+        sqlx::query_as!(
+            Room,
+            r#"
+            UPDATE rooms SET current_occupants = $1, status = $2
+            WHERE id = $3
+            RETURNING id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, building, status AS "status: RoomStatus"
+            "#,
+            current_occupants,
+            status as RoomStatus,
+            id
+        )
+        .fetch_one(executor)
+        .await
+    }
+
     pub async fn find_available(
         pool: &PgPool,
         faculty: Option<&str>,
@@ -49,7 +101,7 @@ impl RoomRepository {
         sqlx::query_as!(
             Room,
             r#"
-            SELECT id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, status
+            SELECT id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, building, status AS "status: RoomStatus"
             FROM rooms
             WHERE status = 'available'
             AND (faculty_restriction IS NULL OR faculty_restriction = $1)
@@ -63,4 +115,26 @@ impl RoomRepository {
         .fetch_all(pool)
         .await
     }
+
+    /// Moves the room to `status` if its current status allows it. Returns
+    /// `None` when the room does not exist or the transition is not allowed.
+    pub async fn update_status(
+        pool: &PgPool,
+        id: &uuid::Uuid,
+        status: RoomStatus,
+    ) -> Result<Option<Room>, sqlx::Error> {
+        sqlx::query_as!(
+            Room,
+            r#"
+            UPDATE rooms SET status = $1
+            WHERE id = $2 AND status = ANY($3)
+            RETURNING id, number, description, photo_url, capacity, current_occupants, faculty_restriction, course_restriction, sex_restriction, building, status AS "status: RoomStatus"
+            "#,
+            status as RoomStatus,
+            id,
+            &status.sources() as &[RoomStatus]
+        )
+        .fetch_optional(pool)
+        .await
+    }
 }
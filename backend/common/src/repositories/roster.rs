@@ -0,0 +1,310 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::roster::{RosterChange, RosterChangeKind, RosterEntry, RosterFlag, RosterImport};
+
+pub struct RosterRepository;
+
+impl RosterRepository {
+  /// Blocks other imports until the transaction ends, so each one diffs
+  /// against the roster the previous one left behind.
+  pub async fn lock<'e, E: PgExecutor<'e>>(executor: E) -> Result<(), sqlx::Error> {
+    sqlx::query!("LOCK TABLE roster_entries IN EXCLUSIVE MODE")
+      .execute(executor)
+      .await?;
+    Ok(())
+  }
+
+  pub async fn find_all<'e, E: PgExecutor<'e>>(executor: E) -> Result<Vec<RosterEntry>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterEntry,
+      r#"
+            SELECT student_id, email, faculty, course, import_id, updated_at
+            FROM roster_entries
+            "#
+    )
+    .fetch_all(executor)
+    .await
+  }
+
+  pub async fn find_by_email(pool: &PgPool, email: &str) -> Result<Option<RosterEntry>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterEntry,
+      r#"
+            SELECT student_id, email, faculty, course, import_id, updated_at
+            FROM roster_entries WHERE LOWER(email) = LOWER($1)
+            "#,
+      email
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  pub async fn create_import<'e, E: PgExecutor<'e>>(
+    executor: E,
+    import: &RosterImport,
+  ) -> Result<RosterImport, sqlx::Error> {
+    sqlx::query_as!(
+      RosterImport,
+      r#"
+            INSERT INTO roster_imports (id, imported_by, imported_at, accepted_rows, rejected_rows)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, imported_by, imported_at, accepted_rows, rejected_rows
+            "#,
+      import.id,
+      import.imported_by,
+      import.imported_at,
+      import.accepted_rows,
+      import.rejected_rows
+    )
+    .fetch_one(executor)
+    .await
+  }
+
+  pub async fn find_imports(pool: &PgPool) -> Result<Vec<RosterImport>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterImport,
+      r#"
+            SELECT id, imported_by, imported_at, accepted_rows, rejected_rows
+            FROM roster_imports ORDER BY imported_at DESC
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  /// Stores an import's diff. `user_id` is filled in from the account
+  /// registered with each email; the value on `changes` is ignored.
+  pub async fn record_changes<'e, E: PgExecutor<'e>>(
+    executor: E,
+    import_id: &Uuid,
+    changes: &[RosterChange],
+  ) -> Result<Vec<RosterChange>, sqlx::Error> {
+    let student_ids: Vec<String> = changes.iter().map(|c| c.student_id.clone()).collect();
+    let kinds: Vec<RosterChangeKind> = changes.iter().map(|c| c.kind).collect();
+    let emails: Vec<String> = changes.iter().map(|c| c.email.clone()).collect();
+    let old_faculties: Vec<Option<String>> = changes.iter().map(|c| c.old_faculty.clone()).collect();
+    let new_faculties: Vec<Option<String>> = changes.iter().map(|c| c.new_faculty.clone()).collect();
+    let old_courses: Vec<Option<i32>> = changes.iter().map(|c| c.old_course).collect();
+    let new_courses: Vec<Option<i32>> = changes.iter().map(|c| c.new_course).collect();
+
+    sqlx::query_as!(
+      RosterChange,
+      r#"
+            INSERT INTO roster_changes (import_id, student_id, kind, email, old_faculty, new_faculty, old_course, new_course, user_id)
+            SELECT $1, n.student_id, n.kind, n.email, n.old_faculty, n.new_faculty, n.old_course, n.new_course, u.id
+            FROM UNNEST($2::varchar[], $3::roster_change_kind[], $4::varchar[], $5::varchar[], $6::varchar[], $7::int4[], $8::int4[])
+                AS n(student_id, kind, email, old_faculty, new_faculty, old_course, new_course)
+            LEFT JOIN users u ON LOWER(u.email) = LOWER(n.email)
+            RETURNING import_id, student_id, kind AS "kind: RosterChangeKind", email, old_faculty, new_faculty, old_course, new_course, user_id
+            "#,
+      import_id,
+      &student_ids,
+      &kinds as &[RosterChangeKind],
+      &emails,
+      &old_faculties as &[Option<String>],
+      &new_faculties as &[Option<String>],
+      &old_courses as &[Option<i32>],
+      &new_courses as &[Option<i32>]
+    )
+    .fetch_all(executor)
+    .await
+  }
+
+  pub async fn find_changes(pool: &PgPool, import_id: &Uuid) -> Result<Vec<RosterChange>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterChange,
+      r#"
+            SELECT import_id, student_id, kind AS "kind: RosterChangeKind", email, old_faculty, new_faculty, old_course, new_course, user_id
+            FROM roster_changes WHERE import_id = $1
+            ORDER BY kind, student_id
+            "#,
+      import_id
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  pub async fn delete_entries<'e, E: PgExecutor<'e>>(
+    executor: E,
+    student_ids: &[String],
+  ) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+      "DELETE FROM roster_entries WHERE student_id = ANY($1)",
+      student_ids
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+  }
+
+  /// Upserts entries by student ID. An email that moved to another student
+  /// ID is taken away from the old entry first.
+  pub async fn upsert_entries(
+    tx: &mut sqlx::PgConnection,
+    entries: &[RosterEntry],
+  ) -> Result<(), sqlx::Error> {
+    let student_ids: Vec<String> = entries.iter().map(|e| e.student_id.clone()).collect();
+    let emails: Vec<String> = entries.iter().map(|e| e.email.clone()).collect();
+    let faculties: Vec<String> = entries.iter().map(|e| e.faculty.clone()).collect();
+    let courses: Vec<i32> = entries.iter().map(|e| e.course).collect();
+    let import_ids: Vec<Uuid> = entries.iter().map(|e| e.import_id).collect();
+    let updated_at: Vec<DateTime<Utc>> = entries.iter().map(|e| e.updated_at).collect();
+
+    sqlx::query!(
+      r#"
+            DELETE FROM roster_entries r
+            USING UNNEST($1::varchar[], $2::varchar[]) AS n(student_id, email)
+            WHERE LOWER(r.email) = LOWER(n.email) AND r.student_id <> n.student_id
+            "#,
+      &student_ids,
+      &emails
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+      r#"
+            INSERT INTO roster_entries (student_id, email, faculty, course, import_id, updated_at)
+            SELECT * FROM UNNEST($1::varchar[], $2::varchar[], $3::varchar[], $4::int4[], $5::uuid[], $6::timestamptz[])
+            ON CONFLICT (student_id) DO UPDATE
+            SET email = EXCLUDED.email, faculty = EXCLUDED.faculty, course = EXCLUDED.course,
+                import_id = EXCLUDED.import_id, updated_at = EXCLUDED.updated_at
+            "#,
+      &student_ids,
+      &emails,
+      &faculties,
+      &courses,
+      &import_ids,
+      &updated_at
+    )
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+  }
+
+  /// Copies changed faculties and courses from an import into the matching
+  /// student profiles.
+  pub async fn update_profiles<'e, E: PgExecutor<'e>>(
+    executor: E,
+    import_id: &Uuid,
+    updated_at: DateTime<Utc>,
+  ) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+      r#"
+            UPDATE student_profiles p
+            SET faculty = c.new_faculty, course = c.new_course, updated_at = $2
+            FROM roster_changes c
+            WHERE c.import_id = $1 AND c.kind = 'changed' AND p.user_id = c.user_id
+            "#,
+      import_id,
+      updated_at
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+  }
+
+  /// Flags the users an import removed, remembering their current residency.
+  /// An unresolved flag is kept as it is; a resolved one is reopened.
+  pub async fn flag_removed_users<'e, E: PgExecutor<'e>>(
+    executor: E,
+    import_id: &Uuid,
+    flagged_at: DateTime<Utc>,
+  ) -> Result<Vec<RosterFlag>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterFlag,
+      r#"
+            INSERT INTO roster_flags (user_id, import_id, flagged_at, residency_id)
+            SELECT c.user_id, c.import_id, $2, r.id
+            FROM roster_changes c
+            LEFT JOIN residencies r ON r.user_id = c.user_id AND r.moved_out_at IS NULL
+            WHERE c.import_id = $1 AND c.kind = 'removed' AND c.user_id IS NOT NULL
+            ON CONFLICT (user_id) DO UPDATE
+            SET import_id = EXCLUDED.import_id, flagged_at = EXCLUDED.flagged_at, residency_id = EXCLUDED.residency_id,
+                resolved_at = NULL, resolved_by = NULL, resolution = NULL
+            WHERE roster_flags.resolved_at IS NOT NULL
+            RETURNING user_id, import_id, flagged_at, residency_id, resolved_at, resolved_by, resolution
+            "#,
+      import_id,
+      flagged_at
+    )
+    .fetch_all(executor)
+    .await
+  }
+
+  /// Drops unresolved flags of users an import put back on the roster.
+  pub async fn unflag_added_users<'e, E: PgExecutor<'e>>(
+    executor: E,
+    import_id: &Uuid,
+  ) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+      r#"
+            DELETE FROM roster_flags f
+            USING roster_changes c
+            WHERE c.import_id = $1 AND c.kind = 'added'
+            AND f.user_id = c.user_id AND f.resolved_at IS NULL
+            "#,
+      import_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+  }
+
+  /// Unresolved flags, oldest first.
+  pub async fn find_flags(pool: &PgPool) -> Result<Vec<RosterFlag>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterFlag,
+      r#"
+            SELECT user_id, import_id, flagged_at, residency_id, resolved_at, resolved_by, resolution
+            FROM roster_flags WHERE resolved_at IS NULL
+            ORDER BY flagged_at
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  /// Unresolved flags of users who still live in the room they had when
+  /// they were flagged.
+  pub async fn find_review_queue(pool: &PgPool) -> Result<Vec<RosterFlag>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterFlag,
+      r#"
+            SELECT f.user_id, f.import_id, f.flagged_at, f.residency_id, f.resolved_at, f.resolved_by, f.resolution
+            FROM roster_flags f
+            JOIN residencies r ON r.id = f.residency_id AND r.moved_out_at IS NULL
+            WHERE f.resolved_at IS NULL
+            ORDER BY f.flagged_at
+            "#
+    )
+    .fetch_all(pool)
+    .await
+  }
+
+  /// Closes a flag. Returns `None` if the user has no unresolved flag.
+  pub async fn resolve_flag(
+    pool: &PgPool,
+    user_id: &Uuid,
+    resolved_by: &Uuid,
+    resolution: &str,
+    resolved_at: DateTime<Utc>,
+  ) -> Result<Option<RosterFlag>, sqlx::Error> {
+    sqlx::query_as!(
+      RosterFlag,
+      r#"
+            UPDATE roster_flags SET resolved_at = $4, resolved_by = $2, resolution = $3
+            WHERE user_id = $1 AND resolved_at IS NULL
+            RETURNING user_id, import_id, flagged_at, residency_id, resolved_at, resolved_by, resolution
+            "#,
+      user_id,
+      resolved_by,
+      resolution,
+      resolved_at
+    )
+    .fetch_optional(pool)
+    .await
+  }
+}
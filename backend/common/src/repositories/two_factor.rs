@@ -0,0 +1,152 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::two_factor::TotpCredential;
+
+pub struct TwoFactorRepository;
+
+impl TwoFactorRepository {
+  pub async fn find(pool: &PgPool, user_id: &Uuid) -> Result<Option<TotpCredential>, sqlx::Error> {
+    sqlx::query_as!(
+      TotpCredential,
+      r#"
+            SELECT user_id, secret, created_at, confirmed_at, last_used_step
+            FROM totp_credentials WHERE user_id = $1
+            "#,
+      user_id
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  /// Whether the user finished enrolling.
+  pub async fn is_enabled(pool: &PgPool, user_id: &Uuid) -> Result<bool, sqlx::Error> {
+    let enabled = sqlx::query_scalar!(
+      r#"
+            SELECT EXISTS (
+                SELECT 1 FROM totp_credentials WHERE user_id = $1 AND confirmed_at IS NOT NULL
+            ) AS "enabled!"
+            "#,
+      user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(enabled)
+  }
+
+  /// Starts enrollment with a new secret, replacing an unfinished one.
+  /// Returns `None` if the user is already enrolled.
+  pub async fn start_enrollment(
+    pool: &PgPool,
+    user_id: &Uuid,
+    secret: &[u8],
+    now: DateTime<Utc>,
+  ) -> Result<Option<TotpCredential>, sqlx::Error> {
+    sqlx::query_as!(
+      TotpCredential,
+      r#"
+            INSERT INTO totp_credentials (user_id, secret, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret = EXCLUDED.secret, created_at = EXCLUDED.created_at, last_used_step = NULL
+            WHERE totp_credentials.confirmed_at IS NULL
+            RETURNING user_id, secret, created_at, confirmed_at, last_used_step
+            "#,
+      user_id,
+      secret,
+      now
+    )
+    .fetch_optional(pool)
+    .await
+  }
+
+  /// Records that a code for `step` was accepted, unless one for this or a
+  /// later step already was. Returns whether the code may be used.
+  pub async fn use_step<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: &Uuid,
+    step: i64,
+  ) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+      r#"
+            UPDATE totp_credentials SET last_used_step = $2
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+      user_id,
+      step
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() == 1)
+  }
+
+  pub async fn confirm<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: &Uuid,
+    confirmed_at: DateTime<Utc>,
+  ) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+      "UPDATE totp_credentials SET confirmed_at = $2 WHERE user_id = $1",
+      user_id,
+      confirmed_at
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+  }
+
+  /// Removes the secret and the recovery codes.
+  pub async fn delete(conn: &mut PgConnection, user_id: &Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+      .execute(&mut *conn)
+      .await?;
+    sqlx::query!("DELETE FROM totp_credentials WHERE user_id = $1", user_id)
+      .execute(&mut *conn)
+      .await?;
+    Ok(())
+  }
+
+  /// Swaps the user's recovery codes for a new set.
+  pub async fn replace_recovery_codes(
+    conn: &mut PgConnection,
+    user_id: &Uuid,
+    code_hashes: &[String],
+  ) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+      .execute(&mut *conn)
+      .await?;
+    sqlx::query!(
+      r#"
+            INSERT INTO recovery_codes (user_id, code_hash)
+            SELECT $1, UNNEST($2::VARCHAR[])
+            "#,
+      user_id,
+      code_hashes
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+  }
+
+  /// Spends an unused recovery code. Returns whether there was one.
+  pub async fn use_recovery_code(
+    pool: &PgPool,
+    user_id: &Uuid,
+    code_hash: &str,
+    used_at: DateTime<Utc>,
+  ) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+      r#"
+            UPDATE recovery_codes SET used_at = $3
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+      user_id,
+      code_hash,
+      used_at
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+  }
+}
@@ -1,12 +1,13 @@
 use crate::models::user::{User, UserRole, UserStatus};
-use sqlx::{Error, PgPool};
+use chrono::{DateTime, Utc};
+use sqlx::{Error, PgExecutor, PgPool};
 use uuid::Uuid;
 
 pub struct UserRepository;
 
 impl UserRepository {
-  pub async fn create(
-    pool: &PgPool,
+  pub async fn create<'e, E: PgExecutor<'e>>(
+    executor: E,
     email: &str,
     password_hash: &str,
     role: UserRole,
@@ -24,7 +25,7 @@ impl UserRepository {
     .bind(password_hash)
     .bind(role)
     .bind(status)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await
   }
 
@@ -32,7 +33,7 @@ impl UserRepository {
     sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, password_hash, role AS "role: _", status AS "status: _", created_at, updated_at
+            SELECT id, email, password_hash, role AS "role: _", status AS "status: _", created_at, updated_at, email_confirmed_at
             FROM users WHERE id = $1
             "#,
             id
@@ -66,4 +67,43 @@ impl UserRepository {
     .fetch_one(pool)
     .await
   }
+
+  /// Marks the user's email as confirmed; an earlier confirmation is kept.
+  pub async fn confirm_email<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: &Uuid,
+    confirmed_at: DateTime<Utc>,
+  ) -> Result<User, Error> {
+    sqlx::query_as::<_, User>(
+      r#"
+            UPDATE users
+            SET email_confirmed_at = COALESCE(email_confirmed_at, $1), updated_at = NOW()
+            WHERE id = $2
+            RETURNING *
+            "#,
+    )
+    .bind(confirmed_at)
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+  }
+
+  pub async fn update_password<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: &Uuid,
+    password_hash: &str,
+  ) -> Result<User, Error> {
+    sqlx::query_as::<_, User>(
+      r#"
+            UPDATE users
+            SET password_hash = $1, updated_at = NOW()
+            WHERE id = $2
+            RETURNING *
+            "#,
+    )
+    .bind(password_hash)
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+  }
 }
@@ -1,43 +1,233 @@
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+  decode, decode_header, encode,
+  jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+  },
+  Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use rsa::{pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::config::env::{Config, JwtAlgorithm};
+
+/// Lifetime of an access token in seconds.
+pub const ACCESS_TOKEN_TTL: u64 = 3600;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
   pub sub: String,  // user_id
   pub role: String, // "student" or "admin"
   pub exp: usize,   // Expiration time
+  pub iat: usize,   // Issued at
+  pub jti: String,  // Token id, used for revocation
+  /// Issue time in milliseconds, so revocation cutoffs are not limited to
+  /// whole seconds. Zero in tokens issued before it existed.
+  #[serde(default)]
+  pub iat_ms: u64,
+  /// Whether the login passed a second factor.
+  #[serde(default)]
+  pub mfa: bool,
+}
+
+impl Claims {
+  /// Issue time in milliseconds, falling back to `iat` for older tokens.
+  pub fn issued_at_ms(&self) -> u64 {
+    if self.iat_ms > 0 {
+      self.iat_ms
+    } else {
+      self.iat as u64 * 1000
+    }
+  }
+
+  /// The authenticated user's id, if `sub` is a valid UUID.
+  pub fn user_id(&self) -> Option<uuid::Uuid> {
+    uuid::Uuid::parse_str(&self.sub).ok()
+  }
+}
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo; the raw key follows it.
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+/// Keys a service signs and verifies access tokens with.
+///
+/// With `hs256` one shared secret does both. Otherwise tokens carry the
+/// `kid` of the private key that signed them and are checked against the
+/// matching public key, so services that only verify never hold anything
+/// that could mint a token.
+pub struct JwtKeys {
+  algorithm: Algorithm,
+  signing: Option<(Option<String>, EncodingKey)>,
+  verifying: HashMap<Option<String>, DecodingKey>,
+  jwks: JwkSet,
+}
+
+impl JwtKeys {
+  pub fn from_config(config: &Config) -> Result<Self, String> {
+    match config.jwt_algorithm {
+      JwtAlgorithm::Hs256 => {
+        let secret = config
+          .jwt_secret
+          .as_deref()
+          .ok_or("JWT_SECRET is required for hs256")?;
+        Ok(JwtKeys {
+          algorithm: Algorithm::HS256,
+          signing: Some((None, EncodingKey::from_secret(secret.as_bytes()))),
+          verifying: HashMap::from([(None, DecodingKey::from_secret(secret.as_bytes()))]),
+          jwks: JwkSet { keys: Vec::new() },
+        })
+      }
+      JwtAlgorithm::Rs256 | JwtAlgorithm::EdDsa => Self::asymmetric(config),
+    }
+  }
+
+  fn asymmetric(config: &Config) -> Result<Self, String> {
+    let algorithm = match config.jwt_algorithm {
+      JwtAlgorithm::Rs256 => Algorithm::RS256,
+      _ => Algorithm::EdDSA,
+    };
+
+    let mut verifying = HashMap::new();
+    let mut keys = Vec::new();
+    for entry in &config.jwt_public_keys {
+      let (kid, path) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("Public key {:?} is not a kid=path pair", entry))?;
+      let pem = read_key(path)?;
+      let jwk = public_jwk(algorithm, kid, &pem).map_err(|e| format!("{}: {}", path, e))?;
+      let key = DecodingKey::from_jwk(&jwk).map_err(|e| format!("{}: {}", path, e))?;
+      if verifying.insert(Some(kid.to_string()), key).is_some() {
+        return Err(format!("Public key ID {} is listed twice", kid));
+      }
+      keys.push(jwk);
+    }
+    if keys.is_empty() {
+      return Err("JWT_PUBLIC_KEYS must list at least one key".to_string());
+    }
+
+    let signing = match &config.jwt_private_key {
+      Some(path) => {
+        let kid = config
+          .jwt_key_id
+          .clone()
+          .ok_or("JWT_KEY_ID is required with JWT_PRIVATE_KEY")?;
+        if !verifying.contains_key(&Some(kid.clone())) {
+          return Err(format!("Signing key {} is not among JWT_PUBLIC_KEYS", kid));
+        }
+        let pem = read_key(path)?;
+        let key = match algorithm {
+          Algorithm::RS256 => EncodingKey::from_rsa_pem(pem.as_bytes()),
+          _ => EncodingKey::from_ed_pem(pem.as_bytes()),
+        }
+        .map_err(|e| format!("{}: {}", path, e))?;
+        Some((Some(kid), key))
+      }
+      None => None,
+    };
+
+    Ok(JwtKeys {
+      algorithm,
+      signing,
+      verifying,
+      jwks: JwkSet { keys },
+    })
+  }
+
+  /// Whether this service can issue tokens, not only check them.
+  pub fn can_sign(&self) -> bool {
+    self.signing.is_some()
+  }
+
+  /// Public keys to publish; empty with `hs256`.
+  pub fn jwks(&self) -> &JwkSet {
+    &self.jwks
+  }
+}
+
+fn read_key(path: &str) -> Result<String, String> {
+  std::fs::read_to_string(path).map_err(|e| format!("Cannot read key {}: {}", path, e))
+}
+
+/// The public key in `pem` (SubjectPublicKeyInfo) as a JWK.
+fn public_jwk(algorithm: Algorithm, kid: &str, pem: &str) -> Result<Jwk, String> {
+  let parameters = match algorithm {
+    Algorithm::RS256 => {
+      let key = RsaPublicKey::from_public_key_pem(pem).map_err(|e| e.to_string())?;
+      AlgorithmParameters::RSA(RSAKeyParameters {
+        key_type: RSAKeyType::RSA,
+        n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+        e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+      })
+    }
+    _ => {
+      let der = pem::parse(pem).map_err(|e| e.to_string())?;
+      let x = der
+        .contents()
+        .strip_prefix(&ED25519_SPKI_PREFIX[..])
+        .filter(|x| x.len() == 32)
+        .ok_or("Not an Ed25519 public key")?;
+      AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+        key_type: OctetKeyPairType::OctetKeyPair,
+        curve: EllipticCurve::Ed25519,
+        x: URL_SAFE_NO_PAD.encode(x),
+      })
+    }
+  };
+  Ok(Jwk {
+    common: CommonParameters {
+      public_key_use: Some(PublicKeyUse::Signature),
+      key_algorithm: Some(match algorithm {
+        Algorithm::RS256 => KeyAlgorithm::RS256,
+        _ => KeyAlgorithm::EdDSA,
+      }),
+      key_id: Some(kid.to_string()),
+      ..Default::default()
+    },
+    algorithm: parameters,
+  })
 }
 
 pub fn create_jwt(
   user_id: &str,
   role: &str,
-  secret: &str,
+  mfa: bool,
+  keys: &JwtKeys,
 ) -> Result<String, jsonwebtoken::errors::Error> {
-  let expiration = SystemTime::now()
+  let (kid, key) = keys
+    .signing
+    .as_ref()
+    .ok_or(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)?;
+  let now_ms = SystemTime::now()
     .duration_since(UNIX_EPOCH)
     .unwrap()
-    .as_secs()
-    + 3600; // 1 hour
+    .as_millis() as u64;
+  let now = now_ms / 1000;
 
   let claims = Claims {
     sub: user_id.to_string(),
     role: role.to_string(),
-    exp: expiration as usize,
+    exp: (now + ACCESS_TOKEN_TTL) as usize,
+    iat: now as usize,
+    jti: uuid::Uuid::new_v4().to_string(),
+    iat_ms: now_ms,
+    mfa,
   };
 
-  encode(
-    &Header::default(),
-    &claims,
-    &EncodingKey::from_secret(secret.as_ref()),
-  )
+  let mut header = Header::new(keys.algorithm);
+  header.kid = kid.clone();
+  encode(&header, &claims, key)
 }
 
-pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-  decode::<Claims>(
-    token,
-    &DecodingKey::from_secret(secret.as_ref()),
-    &Validation::default(),
-  )
-  .map(|data| data.claims)
+/// Checks the token against the key named by its `kid`. Tokens signed with
+/// any other algorithm than the configured one are rejected.
+pub fn verify_jwt(token: &str, keys: &JwtKeys) -> Result<Claims, jsonwebtoken::errors::Error> {
+  let kid = decode_header(token)?.kid;
+  let key = keys
+    .verifying
+    .get(&kid)
+    .ok_or(jsonwebtoken::errors::ErrorKind::InvalidSignature)?;
+  decode::<Claims>(token, key, &Validation::new(keys.algorithm)).map(|data| data.claims)
 }
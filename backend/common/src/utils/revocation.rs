@@ -0,0 +1,54 @@
+use redis::{AsyncCommands, Client};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utils::jwt::{Claims, ACCESS_TOKEN_TTL};
+
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs()
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_millis() as u64
+}
+
+fn token_key(jti: &str) -> String {
+  format!("revoked:{}", jti)
+}
+
+fn user_key(user_id: &str) -> String {
+  format!("revoked_before_ms:{}", user_id)
+}
+
+/// Denylists a single access token until it would have expired anyway.
+pub async fn revoke_token(redis: &Client, claims: &Claims) -> Result<(), redis::RedisError> {
+  let ttl = (claims.exp as u64).saturating_sub(now()).max(1);
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  conn.set_ex(token_key(&claims.jti), 1, ttl).await
+}
+
+/// Revokes every access token of the user issued up to now.
+///
+/// The cutoff is stored in milliseconds and compared with the token's
+/// `iat_ms`, so a session opened right after a password reset or a "log out
+/// everywhere" stays valid even within the same second.
+pub async fn revoke_user_tokens(redis: &Client, user_id: &str) -> Result<(), redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  conn.set_ex(user_key(user_id), now_ms(), ACCESS_TOKEN_TTL).await
+}
+
+/// Whether the token was revoked on its own or by a "log out everywhere".
+pub async fn is_revoked(redis: &Client, claims: &Claims) -> Result<bool, redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  let (revoked, cutoff): (Option<u8>, Option<u64>) = redis::pipe()
+    .get(token_key(&claims.jti))
+    .get(user_key(&claims.sub))
+    .query_async(&mut conn)
+    .await?;
+  Ok(revoked.is_some() || cutoff.is_some_and(|cutoff| claims.issued_at_ms() <= cutoff))
+}
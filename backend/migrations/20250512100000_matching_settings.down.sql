@@ -0,0 +1,2 @@
+ALTER TABLE applications DROP COLUMN matching_settings_version;
+DROP TABLE matching_settings;
@@ -0,0 +1,26 @@
+-- Версии весов критериев совместимости
+CREATE TABLE matching_settings (
+    version SERIAL PRIMARY KEY,
+    wake_hours_weight DOUBLE PRECISION NOT NULL,
+    mbti_weight DOUBLE PRECISION NOT NULL,
+    hobbies_weight DOUBLE PRECISION NOT NULL,
+    age_weight DOUBLE PRECISION NOT NULL,
+    faculty_weight DOUBLE PRECISION NOT NULL,
+    course_weight DOUBLE PRECISION NOT NULL,
+    min_score DOUBLE PRECISION NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    activated_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Активной может быть только одна версия
+CREATE UNIQUE INDEX matching_settings_single_active ON matching_settings (is_active) WHERE is_active;
+
+INSERT INTO matching_settings (
+    wake_hours_weight, mbti_weight, hobbies_weight, age_weight, faculty_weight, course_weight,
+    min_score, is_active, activated_at, created_at
+)
+VALUES (0.25, 0.15, 0.25, 0.15, 0.1, 0.1, 0, TRUE, NOW(), NOW());
+
+ALTER TABLE applications
+    ADD COLUMN matching_settings_version INTEGER REFERENCES matching_settings(version);
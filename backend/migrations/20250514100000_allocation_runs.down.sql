@@ -0,0 +1,2 @@
+ALTER TABLE applications DROP COLUMN allocation_run_id;
+DROP TABLE allocation_runs;
@@ -0,0 +1,11 @@
+-- Запуски пакетного распределения по комнатам
+CREATE TABLE allocation_runs (
+    id UUID PRIMARY KEY,
+    mode VARCHAR NOT NULL,
+    matching_settings_version INTEGER REFERENCES matching_settings(version),
+    summary JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+ALTER TABLE applications
+    ADD COLUMN allocation_run_id UUID REFERENCES allocation_runs(id);
@@ -0,0 +1,2 @@
+DROP TABLE social_benefits;
+DROP TABLE room_preferences;
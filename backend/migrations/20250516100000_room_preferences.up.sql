@@ -0,0 +1,16 @@
+-- Ранжированные предпочтения студентов по комнатам
+CREATE TABLE room_preferences (
+    user_id UUID NOT NULL REFERENCES users(id),
+    room_id UUID NOT NULL REFERENCES rooms(id),
+    rank INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (user_id, rank),
+    UNIQUE (user_id, room_id)
+);
+
+-- Льготные категории, дающие приоритет при распределении
+CREATE TABLE social_benefits (
+    user_id UUID PRIMARY KEY REFERENCES users(id),
+    category VARCHAR NOT NULL,
+    granted_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
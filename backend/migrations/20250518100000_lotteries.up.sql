@@ -0,0 +1,18 @@
+-- Жеребьёвки: зерно публикуется до розыгрыша
+CREATE TABLE lotteries (
+    id UUID PRIMARY KEY,
+    seed VARCHAR NOT NULL,
+    announced_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    drawn_at TIMESTAMP WITH TIME ZONE,
+    allocation_run_id UUID REFERENCES allocation_runs(id)
+);
+
+-- Порядок вытягивания претендентов по каждой комнате
+CREATE TABLE lottery_draws (
+    lottery_id UUID NOT NULL REFERENCES lotteries(id),
+    room_id UUID NOT NULL REFERENCES rooms(id),
+    position INTEGER NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id),
+    admitted BOOLEAN NOT NULL,
+    PRIMARY KEY (lottery_id, room_id, position)
+);
@@ -0,0 +1,8 @@
+ALTER TABLE applications
+    ALTER COLUMN status TYPE VARCHAR USING status::text;
+
+ALTER TABLE rooms
+    ALTER COLUMN status TYPE VARCHAR USING status::text;
+
+DROP TYPE application_status;
+DROP TYPE room_status;
@@ -0,0 +1,11 @@
+-- Статусы комнат и заявок как ENUM вместо произвольных строк
+CREATE TYPE room_status AS ENUM ('available', 'reserved', 'occupied', 'maintenance');
+CREATE TYPE application_status AS ENUM (
+    'pending', 'approved', 'rejected', 'withdrawn', 'checked_in', 'checked_out'
+);
+
+ALTER TABLE rooms
+    ALTER COLUMN status TYPE room_status USING status::room_status;
+
+ALTER TABLE applications
+    ALTER COLUMN status TYPE application_status USING status::application_status;
@@ -0,0 +1,13 @@
+-- Кто где живёт: запись создаётся при одобрении заявки
+CREATE TABLE residencies (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id),
+    room_id UUID NOT NULL REFERENCES rooms(id),
+    application_id UUID REFERENCES applications(id),
+    moved_in_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    moved_out_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Не больше одного текущего проживания на студента
+CREATE UNIQUE INDEX residencies_active_user ON residencies (user_id) WHERE moved_out_at IS NULL;
+CREATE INDEX residencies_room ON residencies (room_id);
@@ -0,0 +1,3 @@
+DROP TABLE role_assignments;
+DROP TYPE staff_role;
+ALTER TABLE rooms DROP COLUMN building;
@@ -0,0 +1,20 @@
+-- Корпус, к которому относится комната
+ALTER TABLE rooms ADD COLUMN building VARCHAR;
+
+-- Роли сотрудников; область действия задаётся корпусом или факультетом
+CREATE TYPE staff_role AS ENUM ('super_admin', 'building_manager', 'faculty_coordinator');
+
+CREATE TABLE role_assignments (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role staff_role NOT NULL,
+    building VARCHAR,
+    faculty VARCHAR,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX role_assignments_user ON role_assignments (user_id);
+
+-- Существующие администраторы становятся суперадминистраторами
+INSERT INTO role_assignments (id, user_id, role, created_at)
+SELECT gen_random_uuid(), id, 'super_admin', NOW() FROM users WHERE role = 'admin';
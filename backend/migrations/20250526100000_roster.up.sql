@@ -0,0 +1,20 @@
+-- Загрузки списка студентов от ВУЗа
+CREATE TABLE roster_imports (
+    id UUID PRIMARY KEY,
+    imported_by UUID REFERENCES users(id),
+    imported_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    accepted_rows INTEGER NOT NULL,
+    rejected_rows INTEGER NOT NULL
+);
+
+-- Студенты из последней загрузки, в которой они встречались
+CREATE TABLE roster_entries (
+    student_id VARCHAR PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    faculty VARCHAR(100) NOT NULL,
+    course INTEGER NOT NULL,
+    import_id UUID NOT NULL REFERENCES roster_imports(id),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE UNIQUE INDEX roster_entries_email ON roster_entries (LOWER(email));
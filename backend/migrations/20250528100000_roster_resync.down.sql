@@ -0,0 +1,3 @@
+DROP TABLE roster_flags;
+DROP TABLE roster_changes;
+DROP TYPE roster_change_kind;
@@ -0,0 +1,27 @@
+-- Разница между загрузкой списка и предыдущим состоянием
+CREATE TYPE roster_change_kind AS ENUM ('added', 'removed', 'changed');
+
+CREATE TABLE roster_changes (
+    import_id UUID NOT NULL REFERENCES roster_imports(id),
+    student_id VARCHAR NOT NULL,
+    kind roster_change_kind NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    old_faculty VARCHAR(100),
+    new_faculty VARCHAR(100),
+    old_course INTEGER,
+    new_course INTEGER,
+    -- Пользователь с этим email на момент загрузки
+    user_id UUID REFERENCES users(id),
+    PRIMARY KEY (import_id, student_id)
+);
+
+-- Пользователи, пропавшие из списка; с residency_id попадают в очередь на разбор
+CREATE TABLE roster_flags (
+    user_id UUID PRIMARY KEY REFERENCES users(id),
+    import_id UUID NOT NULL REFERENCES roster_imports(id),
+    flagged_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    residency_id UUID REFERENCES residencies(id),
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    resolved_by UUID REFERENCES users(id),
+    resolution TEXT
+);
@@ -0,0 +1,3 @@
+DROP TABLE email_tokens;
+DROP TYPE email_token_purpose;
+ALTER TABLE users DROP COLUMN email_confirmed_at;
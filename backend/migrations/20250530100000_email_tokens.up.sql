@@ -0,0 +1,17 @@
+-- Подтверждение email; у существующих пользователей адрес считается подтверждённым
+ALTER TABLE users ADD COLUMN email_confirmed_at TIMESTAMP WITH TIME ZONE;
+UPDATE users SET email_confirmed_at = created_at;
+
+CREATE TYPE email_token_purpose AS ENUM ('confirm_email');
+
+-- Одноразовые токены из писем; хранится только SHA-256 токена
+CREATE TABLE email_tokens (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose email_token_purpose NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX email_tokens_user ON email_tokens (user_id, purpose);
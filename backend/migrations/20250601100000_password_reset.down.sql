@@ -0,0 +1,6 @@
+DELETE FROM email_tokens WHERE purpose = 'reset_password';
+ALTER TYPE email_token_purpose RENAME TO email_token_purpose_old;
+CREATE TYPE email_token_purpose AS ENUM ('confirm_email');
+ALTER TABLE email_tokens
+    ALTER COLUMN purpose TYPE email_token_purpose USING purpose::text::email_token_purpose;
+DROP TYPE email_token_purpose_old;
@@ -0,0 +1,2 @@
+-- Токены сброса пароля живут в той же таблице, что и подтверждение email
+ALTER TYPE email_token_purpose ADD VALUE 'reset_password';
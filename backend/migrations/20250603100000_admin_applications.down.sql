@@ -0,0 +1,2 @@
+DROP TABLE admin_applications;
+DROP TYPE admin_application_status;
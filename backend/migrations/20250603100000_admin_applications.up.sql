@@ -0,0 +1,18 @@
+-- Заявки администраторов на доступ; аккаунт создаётся только после одобрения
+CREATE TYPE admin_application_status AS ENUM ('pending', 'approved', 'rejected');
+
+CREATE TABLE admin_applications (
+    id UUID PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    organisation VARCHAR(255) NOT NULL,
+    position VARCHAR(255) NOT NULL,
+    status admin_application_status NOT NULL DEFAULT 'pending',
+    submitted_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    reviewed_by UUID REFERENCES users(id),
+    review_note TEXT
+);
+
+-- Не больше одной нерассмотренной заявки на адрес
+CREATE UNIQUE INDEX admin_applications_pending_email
+    ON admin_applications (LOWER(email)) WHERE status = 'pending';
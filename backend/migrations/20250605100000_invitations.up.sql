@@ -0,0 +1,22 @@
+-- Приглашения по подписанной ссылке; по ним регистрируются сразу с заданной ролью
+CREATE TABLE invitations (
+    id UUID PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    role user_role NOT NULL,
+    -- Для приглашений сотрудников: роль и её область
+    staff_role staff_role,
+    building VARCHAR,
+    faculty VARCHAR(100),
+    invited_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_sent_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    redeemed_at TIMESTAMP WITH TIME ZONE,
+    redeemed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    CHECK ((role = 'admin') = (staff_role IS NOT NULL))
+);
+
+-- Не больше одного действующего приглашения на адрес
+CREATE UNIQUE INDEX invitations_outstanding_email ON invitations (LOWER(email))
+    WHERE redeemed_at IS NULL AND revoked_at IS NULL;
@@ -0,0 +1,17 @@
+-- Секрет TOTP администратора; до первого верного кода подключение не завершено
+CREATE TABLE totp_credentials (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret BYTEA NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+    -- Последний принятый 30-секундный шаг; защищает от повторного ввода кода
+    last_used_step BIGINT
+);
+
+-- Одноразовые коды восстановления; хранится только SHA-256
+CREATE TABLE recovery_codes (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (user_id, code_hash)
+);
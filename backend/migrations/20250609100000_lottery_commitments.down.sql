@@ -0,0 +1,3 @@
+DROP TABLE lottery_preferences;
+DROP INDEX lotteries_one_open;
+ALTER TABLE lotteries DROP COLUMN seed_hash;
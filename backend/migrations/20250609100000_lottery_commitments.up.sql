@@ -0,0 +1,16 @@
+-- До розыгрыша публикуется только хеш зерна; само зерно раскрывается при розыгрыше
+ALTER TABLE lotteries ADD COLUMN seed_hash VARCHAR;
+UPDATE lotteries SET seed_hash = encode(sha256(convert_to(seed, 'UTF8')), 'hex');
+ALTER TABLE lotteries ALTER COLUMN seed_hash SET NOT NULL;
+
+-- Не больше одной объявленной, но не разыгранной жеребьёвки
+CREATE UNIQUE INDEX lotteries_one_open ON lotteries ((TRUE)) WHERE drawn_at IS NULL;
+
+-- Предпочтения, зафиксированные в момент объявления жеребьёвки
+CREATE TABLE lottery_preferences (
+    lottery_id UUID NOT NULL REFERENCES lotteries(id),
+    user_id UUID NOT NULL REFERENCES users(id),
+    room_id UUID NOT NULL REFERENCES rooms(id),
+    rank INTEGER NOT NULL,
+    PRIMARY KEY (lottery_id, user_id, rank)
+);
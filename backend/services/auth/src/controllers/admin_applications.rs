@@ -0,0 +1,295 @@
+use actix_web::{web, HttpResponse};
+use chrono::Duration;
+use dormmatch_common::{
+  config::env::Config,
+  mail::Mailer,
+  middleware::role::StaffAccess,
+  models::{
+    admin_application::{AdminApplication, AdminApplicationStatus},
+    invitation::Invitation,
+    role_assignment::{Permission, Scope, StaffRole},
+    user::UserRole,
+  },
+  repositories::{
+    admin_application::AdminApplicationRepository, invitation::InvitationRepository,
+    user::UserRepository,
+  },
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::Utc, PgPool, Postgres, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::controllers::invitations::{invitation_secret, DEFAULT_INVITATION_DAYS};
+use crate::services::invitation::{send_invitation, sign_invitation};
+
+#[derive(Deserialize, ToSchema)]
+pub struct AdminApplicationRequest {
+  email: String,
+  /// University or office the applicant works for.
+  organisation: String,
+  position: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReviewAdminApplicationRequest {
+  /// Reason for the decision, kept with the application.
+  note: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ApproveAdminApplicationRequest {
+  /// Reason for the decision, kept with the application.
+  note: Option<String>,
+  /// Staff role granted with the approval.
+  staff_role: StaffRole,
+  /// Building of a building manager.
+  building: Option<String>,
+  /// Faculty of a faculty coordinator.
+  faculty: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApprovedAdminApplication {
+  application: AdminApplication,
+  /// Invitation the applicant sets up the account with.
+  invitation: Invitation,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/admin-applications",
+    request_body = AdminApplicationRequest,
+    responses(
+        (status = 201, description = "Application submitted; no account exists until it is approved", body = AdminApplication),
+        (status = 400, description = "Missing details or the email already has an account", body = String),
+        (status = 409, description = "The email already has an application waiting for review", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn apply_admin(
+  req: web::Json<AdminApplicationRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let (email, organisation, position) = (req.email.trim(), req.organisation.trim(), req.position.trim());
+  if email.is_empty() || organisation.is_empty() || position.is_empty() {
+    return Err(actix_web::error::ErrorBadRequest("Email, organisation and position are required"));
+  }
+
+  let existing = UserRepository::find_by_email(&pool, email)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?;
+  if existing.is_some() {
+    return Err(actix_web::error::ErrorBadRequest("User already exists"));
+  }
+
+  let application = AdminApplicationRepository::create(
+    &pool,
+    &AdminApplication {
+      id: Uuid::new_v4(),
+      email: email.to_string(),
+      organisation: organisation.to_string(),
+      position: position.to_string(),
+      status: AdminApplicationStatus::Pending,
+      submitted_at: Utc::now(),
+      reviewed_at: None,
+      reviewed_by: None,
+      review_note: None,
+    },
+  )
+  .await
+  .map_err(|e| match e.as_database_error() {
+    Some(db) if db.is_unique_violation() => {
+      actix_web::error::ErrorConflict("An application for this email is already waiting for review")
+    }
+    _ => actix_web::error::ErrorInternalServerError(format!("Failed to store application: {}", e)),
+  })?;
+
+  Ok(HttpResponse::Created().json(application))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/admin-applications",
+    responses(
+        (status = 200, description = "Applications waiting for review, oldest first", body = [AdminApplication]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_staff permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff"]))
+)]
+pub async fn list_admin_applications(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageStaff, Scope::global())?;
+
+  let applications = AdminApplicationRepository::find_pending(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to list applications: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(applications))
+}
+
+/// Records the decision on a pending application, telling a missing
+/// application apart from one that was decided already.
+async fn review(
+  tx: &mut Transaction<'_, Postgres>,
+  id: &Uuid,
+  status: AdminApplicationStatus,
+  reviewer: &Uuid,
+  note: Option<&str>,
+) -> Result<AdminApplication, actix_web::Error> {
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let reviewed = AdminApplicationRepository::review(&mut **tx, id, status, reviewer, note, Utc::now())
+    .await
+    .map_err(internal("Failed to record decision"))?;
+  if let Some(application) = reviewed {
+    return Ok(application);
+  }
+  match AdminApplicationRepository::find_by_id(&mut **tx, id)
+    .await
+    .map_err(internal("Application lookup failed"))?
+  {
+    Some(_) => Err(actix_web::error::ErrorConflict("Application has already been reviewed")),
+    None => Err(actix_web::error::ErrorNotFound("Application not found")),
+  }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/admin-applications/{id}/approve",
+    params(
+        ("id", Path, description = "Application ID")
+    ),
+    request_body = ApproveAdminApplicationRequest,
+    responses(
+        (status = 200, description = "Application approved and an invitation with the staff role sent; a lost link is resent through /auth/invitations/{id}/resend", body = ApprovedAdminApplication),
+        (status = 400, description = "Staff role and scope do not fit together", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_staff permission", body = String),
+        (status = 404, description = "Application not found", body = String),
+        (status = 409, description = "Application has already been reviewed, or the email has another outstanding invitation", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff"]))
+)]
+pub async fn approve_admin_application(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  req: web::Json<ApproveAdminApplicationRequest>,
+  pool: web::Data<PgPool>,
+  mailer: web::Data<dyn Mailer>,
+  config: web::Data<Config>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageStaff, Scope::global())?;
+  req
+    .staff_role
+    .check_scope(req.building.as_deref(), req.faculty.as_deref())
+    .map_err(actix_web::error::ErrorBadRequest)?;
+  let secret = invitation_secret(&config)?;
+  let reviewer = staff
+    .claims
+    .user_id()
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let now = Utc::now();
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+
+  let application = review(
+    &mut tx,
+    &path.into_inner(),
+    AdminApplicationStatus::Approved,
+    &reviewer,
+    req.note.as_deref(),
+  )
+  .await?;
+  // The applicant sets up the account through an ordinary invitation, which
+  // can be resent or revoked like any other. Its redemption creates the user
+  // and grants the staff role.
+  let invitation = InvitationRepository::create(
+    &mut *tx,
+    &Invitation {
+      id: Uuid::new_v4(),
+      email: application.email.clone(),
+      role: UserRole::Admin,
+      staff_role: Some(req.staff_role),
+      building: req.building.clone(),
+      faculty: req.faculty.clone(),
+      invited_by: Some(reviewer),
+      created_at: now,
+      expires_at: now + Duration::days(DEFAULT_INVITATION_DAYS),
+      last_sent_at: now,
+      redeemed_at: None,
+      redeemed_by: None,
+      revoked_at: None,
+    },
+  )
+  .await
+  .map_err(|e| match e.as_database_error() {
+    Some(db) if db.is_unique_violation() => {
+      actix_web::error::ErrorConflict("This email already has an outstanding invitation; revoke it first")
+    }
+    _ => actix_web::error::ErrorInternalServerError(format!("Failed to create invitation: {}", e)),
+  })?;
+  tx.commit().await.map_err(internal("Failed to commit decision"))?;
+
+  send_invitation(mailer.get_ref(), &config, &invitation, &sign_invitation(secret, &invitation))
+    .await
+    .map_err(|e| {
+      actix_web::error::ErrorInternalServerError(format!(
+        "Application approved, but the invitation was not sent ({}); resend it",
+        e
+      ))
+    })?;
+  Ok(HttpResponse::Ok().json(ApprovedAdminApplication { application, invitation }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/admin-applications/{id}/reject",
+    params(
+        ("id", Path, description = "Application ID")
+    ),
+    request_body = ReviewAdminApplicationRequest,
+    responses(
+        (status = 200, description = "Application rejected; the email may apply again", body = AdminApplication),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_staff permission", body = String),
+        (status = 404, description = "Application not found", body = String),
+        (status = 409, description = "Application has already been reviewed", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff"]))
+)]
+pub async fn reject_admin_application(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  req: web::Json<ReviewAdminApplicationRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageStaff, Scope::global())?;
+  let reviewer = staff
+    .claims
+    .user_id()
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+  let application = review(
+    &mut tx,
+    &path.into_inner(),
+    AdminApplicationStatus::Rejected,
+    &reviewer,
+    req.note.as_deref(),
+  )
+  .await?;
+  tx.commit().await.map_err(internal("Failed to commit decision"))?;
+
+  Ok(HttpResponse::Ok().json(application))
+}
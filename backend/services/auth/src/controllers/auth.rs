@@ -1,22 +1,41 @@
-use actix_web::{web, HttpResponse, Responder};
+use std::sync::OnceLock;
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
 use dormmatch_common::{
+  mail::Mailer,
   models::{
     profile::Sex,
     user::{User, UserRole, UserStatus},
   },
   repositories::{
     profile::{PostgresStudentProfileRepository, StudentProfileRepository},
+    roster::RosterRepository,
+    two_factor::TwoFactorRepository,
     user::UserRepository,
   },
   types::types::{MbtiType, WakeType},
   utils::{
     crypto::{hash_password, verify_password},
-    jwt::create_jwt,
+    jwt::{create_jwt, Claims, JwtKeys},
+    revocation::{revoke_token, revoke_user_tokens},
   },
 };
+use redis::Client;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::services::{
+  auth::{
+    issue_refresh_token, revoke_refresh_token, revoke_user_refresh_tokens, rotate_refresh_token,
+    Refresh,
+  },
+  confirmation::send_confirmation,
+  lockout::{clear, record_failure, retry_after, LoginSubject},
+  roster::roster_verdict,
+  two_factor::issue_challenge,
+};
 
 #[derive(Deserialize, ToSchema)]
 pub struct RegisterStudentRequest {
@@ -36,7 +55,7 @@ pub struct RegisterStudentRequest {
     path = "/auth/register",
     request_body = RegisterStudentRequest,
     responses(
-        (status = 201, description = "User registered successfully", body = User),
+        (status = 201, description = "User registered and a confirmation email sent; verified or rejected right away when the university roster settles it", body = User),
         (status = 400, description = "User already exists", body = String),
         (status = 500, description = "Internal server error", body = String)
     )
@@ -44,7 +63,8 @@ pub struct RegisterStudentRequest {
 pub async fn register_student(
   req: web::Json<RegisterStudentRequest>,
   pool: web::Data<PgPool>,
-  _config: web::Data<dormmatch_common::config::env::Config>,
+  mailer: web::Data<dyn Mailer>,
+  config: web::Data<dormmatch_common::config::env::Config>,
 ) -> impl Responder {
   let password_hash = match hash_password(&req.password) {
     Ok(hash) => hash,
@@ -52,7 +72,7 @@ pub async fn register_student(
   };
 
   let user = UserRepository::create(
-    &pool,
+    pool.get_ref(),
     &req.email,
     &password_hash,
     UserRole::Student,
@@ -64,7 +84,7 @@ pub async fn register_student(
     Ok(user) => {
       let profile = PostgresStudentProfileRepository
         .create(
-          &pool,
+          pool.get_ref(),
           &user.id,
           &req.faculty,
           req.course,
@@ -76,10 +96,33 @@ pub async fn register_student(
         )
         .await;
 
-      match profile {
-        Ok(_) => HttpResponse::Created().json(user),
-        Err(_) => HttpResponse::InternalServerError().body("Failed to create profile"),
+      if profile.is_err() {
+        return HttpResponse::InternalServerError().body("Failed to create profile");
       }
+
+      // Settle the account right away when the roster is conclusive; a
+      // failed lookup just leaves it pending for manual verification.
+      let entry = match RosterRepository::find_by_email(&pool, &req.email).await {
+        Ok(entry) => entry,
+        Err(e) => {
+          tracing::warn!("Roster lookup failed for {}: {}", req.email, e);
+          None
+        }
+      };
+      let user = match roster_verdict(entry.as_ref(), &req.faculty, req.course) {
+        Some(status) => match UserRepository::update_status(&pool, user.id, status).await {
+          Ok(user) => user,
+          Err(_) => return HttpResponse::InternalServerError().body("Failed to update user status"),
+        },
+        None => user,
+      };
+
+      // The account exists either way; the student can ask for another
+      // link through /auth/resend-confirmation.
+      if let Err(e) = send_confirmation(&pool, mailer.get_ref(), &config, &user).await {
+        tracing::warn!("Confirmation email to {} failed: {}", user.email, e);
+      }
+      HttpResponse::Created().json(user)
     }
     Err(_) => HttpResponse::BadRequest().body("User already exists"),
   }
@@ -93,7 +136,23 @@ pub struct LoginRequest {
 
 #[derive(Serialize, ToSchema)]
 pub struct LoginResponse {
+  /// Short-lived access token (JWT).
   token: String,
+  /// Long-lived token for `/auth/refresh`; it can be used only once.
+  refresh_token: String,
+}
+
+/// Answer to a correct password when the account has two-factor
+/// authentication enabled.
+#[derive(Serialize, ToSchema)]
+pub struct TwoFactorChallenge {
+  /// Goes to `/auth/login/2fa` with the code; valid for five minutes.
+  two_factor_token: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshRequest {
+  refresh_token: String,
 }
 
 #[utoipa::path(
@@ -102,34 +161,235 @@ pub struct LoginResponse {
     request_body = LoginRequest,
     responses(
         (status = 200, description = "Login successful", body = LoginResponse),
-        (status = 401, description = "Invalid credentials or user not found", body = String),
+        (status = 202, description = "Password accepted; finish at /auth/login/2fa with a TOTP or recovery code", body = TwoFactorChallenge),
+        (status = 401, description = "Invalid credentials; Retry-After is set once further attempts are throttled", body = String),
+        (status = 403, description = "Email address is not confirmed yet, or the account is pending verification or rejected", body = String),
+        (status = 429, description = "Too many failed attempts for the account or address; see Retry-After", body = String),
         (status = 500, description = "Internal server error", body = String)
     )
 )]
 pub async fn login(
+  http: HttpRequest,
   req: web::Json<LoginRequest>,
   pool: web::Data<PgPool>,
-  config: web::Data<dormmatch_common::config::env::Config>,
+  redis: web::Data<Client>,
+  keys: web::Data<JwtKeys>,
 ) -> impl Responder {
-  let user = UserRepository::find_by_email(&pool, &req.email).await;
+  // Counted per peer address: forwarding headers are set by the client and
+  // would let it pick a fresh counter for every guess.
+  let mut subjects = vec![LoginSubject::Email(&req.email)];
+  subjects.extend(http.peer_addr().map(|addr| LoginSubject::Ip(addr.ip())));
 
-  match user {
-    Ok(Some(user)) => {
-      if verify_password(&req.password, &user.password_hash).is_ok() {
-        let role = match user.role.as_str() {
-          "student" => "student",
-          "admin" => "admin",
-          _ => return HttpResponse::InternalServerError().body("Invalid role"),
-        };
-        match create_jwt(&user.id.to_string(), role, &config.jwt_secret) {
-          Ok(token) => HttpResponse::Ok().json(LoginResponse { token }),
-          Err(_) => HttpResponse::InternalServerError().body("Failed to create JWT"),
+  match retry_after(&redis, &subjects).await {
+    Ok(Some(seconds)) => {
+      return HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, seconds.to_string()))
+        .body("Too many failed login attempts")
+    }
+    Ok(None) => {}
+    Err(_) => return HttpResponse::InternalServerError().body("Session store error"),
+  }
+
+  let user = match UserRepository::find_by_email(&pool, &req.email).await {
+    Ok(user) => user,
+    Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+  };
+  // Unknown emails get the same answer, and take as long, as a wrong password.
+  let hash = user.as_ref().map_or_else(|| dummy_hash(), |user| user.password_hash.as_str());
+  let valid = verify_password(&req.password, hash).unwrap_or(false);
+  let Some(user) = user.filter(|_| valid) else {
+    return match record_failure(&redis, &subjects).await {
+      Ok(delay) => {
+        let mut response = HttpResponse::Unauthorized();
+        if let Some(seconds) = delay {
+          response.insert_header((header::RETRY_AFTER, seconds.to_string()));
         }
-      } else {
-        HttpResponse::Unauthorized().body("Invalid credentials")
+        response.body("Invalid credentials")
       }
+      Err(_) => HttpResponse::InternalServerError().body("Session store error"),
+    };
+  };
+
+  if user.email_confirmed_at.is_none() {
+    return HttpResponse::Forbidden().body("Email address is not confirmed");
+  }
+  if let Some(response) = refuse_unverified(&user) {
+    return response;
+  }
+  if user.role == "admin" {
+    match TwoFactorRepository::is_enabled(&pool, &user.id).await {
+      Ok(true) => {
+        return match issue_challenge(&redis, &user.id.to_string()).await {
+          Ok(two_factor_token) => HttpResponse::Accepted().json(TwoFactorChallenge { two_factor_token }),
+          Err(_) => HttpResponse::InternalServerError().body("Session store error"),
+        }
+      }
+      Ok(false) => {}
+      Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+    }
+  }
+  // With a second factor the counter is only cleared once it is passed.
+  if clear(&redis, &LoginSubject::Email(&user.email)).await.is_err() {
+    return HttpResponse::InternalServerError().body("Session store error");
+  }
+  issue_tokens(&user, &redis, &keys, false).await
+}
+
+/// Refuses accounts still waiting for review or rejected by it.
+fn refuse_unverified(user: &User) -> Option<HttpResponse> {
+  match user.status.as_str() {
+    "verified" => None,
+    "rejected" => Some(HttpResponse::Forbidden().body("Account has been rejected")),
+    _ => Some(HttpResponse::Forbidden().body("Account is pending verification")),
+  }
+}
+
+/// Hash checked against when the email is unknown.
+fn dummy_hash() -> &'static str {
+  static HASH: OnceLock<String> = OnceLock::new();
+  HASH.get_or_init(|| hash_password("not a password").unwrap_or_default())
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "New access and refresh tokens", body = LoginResponse),
+        (status = 401, description = "Refresh token invalid, expired or already used", body = String),
+        (status = 403, description = "Account is pending verification or rejected", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn refresh(
+  req: web::Json<RefreshRequest>,
+  pool: web::Data<PgPool>,
+  redis: web::Data<Client>,
+  keys: web::Data<JwtKeys>,
+) -> impl Responder {
+  let (user_id, refresh_token) = match rotate_refresh_token(&redis, &req.refresh_token).await {
+    Ok(Refresh::Rotated { user_id, token }) => (user_id, token),
+    Ok(Refresh::Invalid) => return HttpResponse::Unauthorized().body("Invalid refresh token"),
+    Ok(Refresh::Reused) => {
+      return HttpResponse::Unauthorized()
+        .body("Refresh token already used; the session it belongs to has been revoked")
     }
-    Ok(None) => HttpResponse::Unauthorized().body("User not found"),
-    Err(_) => HttpResponse::InternalServerError().body("Database error"),
+    Err(_) => return HttpResponse::InternalServerError().body("Session store error"),
+  };
+
+  let user = match Uuid::parse_str(&user_id) {
+    Ok(id) => UserRepository::find_by_id(&pool, &id).await,
+    Err(_) => return HttpResponse::Unauthorized().body("Invalid refresh token"),
+  };
+  let user = match user {
+    Ok(Some(user)) => user,
+    Ok(None) => return HttpResponse::Unauthorized().body("User not found"),
+    Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+  };
+  if let Some(response) = refuse_unverified(&user) {
+    return response;
+  }
+
+  // Enrolling revokes every older session, so an enrolled admin's refresh
+  // token comes from a login that passed the second step.
+  let mfa = match TwoFactorRepository::is_enabled(&pool, &user.id).await {
+    Ok(enabled) => enabled,
+    Err(_) => return HttpResponse::InternalServerError().body("Database error"),
+  };
+  match access_token(&user, mfa, &keys) {
+    Ok(token) => HttpResponse::Ok().json(LoginResponse {
+      token,
+      refresh_token,
+    }),
+    Err(response) => response,
+  }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LogoutRequest {
+  /// Refresh token of this session, revoked along with the access token.
+  refresh_token: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    request_body = LogoutRequest,
+    responses(
+        (status = 204, description = "Access token and, if given, the session's refresh token revoked"),
+        (status = 401, description = "Missing, invalid or revoked token", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(("bearerAuth" = []))
+)]
+pub async fn logout(
+  claims: web::ReqData<Claims>,
+  req: Option<web::Json<LogoutRequest>>,
+  redis: web::Data<Client>,
+) -> impl Responder {
+  if revoke_token(&redis, &claims).await.is_err() {
+    return HttpResponse::InternalServerError().body("Session store error");
+  }
+  if let Some(refresh_token) = req.and_then(|req| req.into_inner().refresh_token) {
+    if revoke_refresh_token(&redis, &claims.sub, &refresh_token).await.is_err() {
+      return HttpResponse::InternalServerError().body("Session store error");
+    }
+  }
+  HttpResponse::NoContent().finish()
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    responses(
+        (status = 204, description = "Every access and refresh token of the user revoked"),
+        (status = 401, description = "Missing, invalid or revoked token", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    ),
+    security(("bearerAuth" = []))
+)]
+pub async fn logout_all(claims: web::ReqData<Claims>, redis: web::Data<Client>) -> impl Responder {
+  let revoked = async {
+    revoke_user_tokens(&redis, &claims.sub).await?;
+    revoke_user_refresh_tokens(&redis, &claims.sub).await
+  };
+  match revoked.await {
+    Ok(()) => HttpResponse::NoContent().finish(),
+    Err(_) => HttpResponse::InternalServerError().body("Session store error"),
+  }
+}
+
+fn access_token(
+  user: &User,
+  mfa: bool,
+  keys: &JwtKeys,
+) -> Result<String, HttpResponse> {
+  let role = match user.role.as_str() {
+    "student" => "student",
+    "admin" => "admin",
+    _ => return Err(HttpResponse::InternalServerError().body("Invalid role")),
+  };
+  create_jwt(&user.id.to_string(), role, mfa, keys)
+    .map_err(|_| HttpResponse::InternalServerError().body("Failed to create JWT"))
+}
+
+/// Access token plus the first refresh token of a new session. `mfa` tells
+/// whether the login passed a second factor.
+pub(crate) async fn issue_tokens(
+  user: &User,
+  redis: &web::Data<Client>,
+  keys: &JwtKeys,
+  mfa: bool,
+) -> HttpResponse {
+  let token = match access_token(user, mfa, keys) {
+    Ok(token) => token,
+    Err(response) => return response,
+  };
+  match issue_refresh_token(redis, &user.id.to_string()).await {
+    Ok(refresh_token) => HttpResponse::Ok().json(LoginResponse {
+      token,
+      refresh_token,
+    }),
+    Err(_) => HttpResponse::InternalServerError().body("Session store error"),
   }
 }
@@ -0,0 +1,96 @@
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  config::env::Config,
+  mail::Mailer,
+  models::{email_token::EmailTokenPurpose, user::User},
+  repositories::{email_token::EmailTokenRepository, user::UserRepository},
+};
+use serde::Deserialize;
+use sqlx::{types::chrono::Utc, PgPool};
+use utoipa::ToSchema;
+
+use crate::services::{
+  auth::hash_token,
+  confirmation::{can_resend, send_confirmation},
+};
+
+#[derive(Deserialize, ToSchema)]
+pub struct ConfirmEmailRequest {
+  /// Token from the confirmation link.
+  token: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResendConfirmationRequest {
+  email: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/confirm-email",
+    request_body = ConfirmEmailRequest,
+    responses(
+        (status = 200, description = "Email confirmed", body = User),
+        (status = 400, description = "Token is unknown, used or expired", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn confirm_email(
+  req: web::Json<ConfirmEmailRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let now = Utc::now();
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+
+  let token = EmailTokenRepository::consume(&mut *tx, &hash_token(&req.token), EmailTokenPurpose::ConfirmEmail, now)
+    .await
+    .map_err(internal("Token lookup failed"))?
+    .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid or expired confirmation token"))?;
+  let user = UserRepository::confirm_email(&mut *tx, &token.user_id, now)
+    .await
+    .map_err(internal("Failed to confirm email"))?;
+
+  tx.commit().await.map_err(internal("Failed to commit confirmation"))?;
+  Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/resend-confirmation",
+    request_body = ResendConfirmationRequest,
+    responses(
+        (status = 202, description = "A new link is on its way if the account exists and is unconfirmed", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn resend_confirmation(
+  req: web::Json<ResendConfirmationRequest>,
+  pool: web::Data<PgPool>,
+  mailer: web::Data<dyn Mailer>,
+  config: web::Data<Config>,
+) -> Result<HttpResponse, actix_web::Error> {
+  // The answer is the same whether or not the account exists, so the
+  // endpoint cannot be used to probe for registered emails.
+  let accepted =
+    HttpResponse::Accepted().body("If the account exists and is unconfirmed, a new link has been sent");
+
+  let user = UserRepository::find_by_email(&pool, &req.email)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?;
+  let Some(user) = user.filter(|user| user.email_confirmed_at.is_none()) else {
+    return Ok(accepted);
+  };
+
+  let due = can_resend(&pool, &user)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Token lookup failed: {}", e)))?;
+  if due {
+    send_confirmation(&pool, mailer.get_ref(), &config, &user)
+      .await
+      .map_err(actix_web::error::ErrorInternalServerError)?;
+  }
+  Ok(accepted)
+}
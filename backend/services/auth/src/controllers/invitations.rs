@@ -0,0 +1,388 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use dormmatch_common::{
+  config::env::Config,
+  mail::Mailer,
+  middleware::role::StaffAccess,
+  models::{
+    invitation::Invitation,
+    profile::Sex,
+    role_assignment::{Permission, RoleAssignment, Scope, StaffRole},
+    user::{User, UserRole, UserStatus},
+  },
+  repositories::{
+    invitation::InvitationRepository,
+    profile::{PostgresStudentProfileRepository, StudentProfileRepository},
+    role_assignment::RoleAssignmentRepository,
+    user::UserRepository,
+  },
+  types::types::{MbtiType, WakeType},
+  utils::crypto::hash_password,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::services::invitation::{send_invitation, sign_invitation, verify_invitation};
+
+/// Validity of an invitation when the request names no expiry, in days.
+pub const DEFAULT_INVITATION_DAYS: i64 = 7;
+
+/// Latest expiry an invitation may be given, in days from now.
+pub const MAX_INVITATION_DAYS: i64 = 30;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateInvitationRequest {
+  email: String,
+  role: UserRole,
+  /// Required for admin invitations.
+  staff_role: Option<StaffRole>,
+  /// Building of an invited building manager.
+  building: Option<String>,
+  /// Faculty of an invited student or faculty coordinator.
+  faculty: Option<String>,
+  /// Defaults to a week from now; at most 30 days ahead.
+  expires_at: Option<DateTime<Utc>>,
+}
+
+impl CreateInvitationRequest {
+  fn validate(&self) -> Result<(), String> {
+    match (self.role, self.staff_role) {
+      (UserRole::Admin, Some(staff_role)) => {
+        staff_role.check_scope(self.building.as_deref(), self.faculty.as_deref())
+      }
+      (UserRole::Admin, None) => Err("An admin invitation needs a staff role".to_string()),
+      (UserRole::Student, Some(_)) => Err("A student invitation cannot carry a staff role".to_string()),
+      (UserRole::Student, None) if self.building.is_some() => {
+        Err("A student invitation cannot be limited to a building".to_string())
+      }
+      (UserRole::Student, None) => Ok(()),
+    }
+  }
+}
+
+/// Profile of an invited student, as asked for at registration.
+#[derive(Deserialize, ToSchema)]
+pub struct InvitedStudentProfile {
+  /// Only needed when the invitation does not name a faculty.
+  faculty: Option<String>,
+  course: i32,
+  gender: Sex,
+  age: i32,
+  wake_hours: WakeType,
+  hobbies: Vec<String>,
+  mbti: Option<MbtiType>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RedeemInvitationRequest {
+  /// Token from the invitation link.
+  token: String,
+  password: String,
+  /// Required for student invitations.
+  profile: Option<InvitedStudentProfile>,
+}
+
+/// What the caller needs to issue or manage an invitation for `role`.
+fn authority(role: UserRole, faculty: Option<&str>) -> (Permission, Scope<'_>) {
+  match role {
+    UserRole::Admin => (Permission::ManageStaff, Scope::global()),
+    UserRole::Student => (Permission::VerifyStudents, Scope::faculty(faculty)),
+  }
+}
+
+pub(crate) fn invitation_secret(config: &Config) -> Result<&str, actix_web::Error> {
+  config
+    .invitation_secret
+    .as_deref()
+    .ok_or_else(|| actix_web::error::ErrorInternalServerError("Invitation signing is not configured"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/invitations",
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 201, description = "Invitation created and its link emailed", body = Invitation),
+        (status = 400, description = "Role, scope or expiry is invalid", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks manage_staff for staff invitations, or verify_students for the student's faculty", body = String),
+        (status = 409, description = "The email is registered or already has an outstanding invitation", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff", "verify_students"]))
+)]
+pub async fn create_invitation(
+  staff: StaffAccess,
+  req: web::Json<CreateInvitationRequest>,
+  pool: web::Data<PgPool>,
+  mailer: web::Data<dyn Mailer>,
+  config: web::Data<Config>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let (permission, scope) = authority(req.role, req.faculty.as_deref());
+  staff.require(permission, scope)?;
+  req.validate().map_err(actix_web::error::ErrorBadRequest)?;
+  let secret = invitation_secret(&config)?;
+
+  let now = Utc::now();
+  let expires_at = req.expires_at.unwrap_or(now + Duration::days(DEFAULT_INVITATION_DAYS));
+  if expires_at <= now || expires_at > now + Duration::days(MAX_INVITATION_DAYS) {
+    return Err(actix_web::error::ErrorBadRequest(format!(
+      "Expiry must be within the next {} days",
+      MAX_INVITATION_DAYS
+    )));
+  }
+
+  let existing = UserRepository::find_by_email(&pool, &req.email)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?;
+  if existing.is_some() {
+    return Err(actix_web::error::ErrorConflict("A user with this email already exists"));
+  }
+
+  let req = req.into_inner();
+  let invitation = InvitationRepository::create(
+    pool.get_ref(),
+    &Invitation {
+      id: Uuid::new_v4(),
+      email: req.email,
+      role: req.role,
+      staff_role: req.staff_role,
+      building: req.building,
+      faculty: req.faculty,
+      invited_by: staff.claims.user_id(),
+      created_at: now,
+      expires_at,
+      last_sent_at: now,
+      redeemed_at: None,
+      redeemed_by: None,
+      revoked_at: None,
+    },
+  )
+  .await
+  .map_err(|e| match e.as_database_error() {
+    Some(db) if db.is_unique_violation() => {
+      actix_web::error::ErrorConflict("This email already has an outstanding invitation")
+    }
+    _ => actix_web::error::ErrorInternalServerError(format!("Failed to create invitation: {}", e)),
+  })?;
+
+  send_invitation(mailer.get_ref(), &config, &invitation, &sign_invitation(secret, &invitation))
+    .await
+    .map_err(|e| {
+      actix_web::error::ErrorInternalServerError(format!(
+        "Invitation created, but the email was not sent ({}); resend it",
+        e
+      ))
+    })?;
+  Ok(HttpResponse::Created().json(invitation))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/invitations",
+    responses(
+        (status = 200, description = "Outstanding invitations the caller may manage, oldest first", body = [Invitation]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller is not an admin", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff", "verify_students"]))
+)]
+pub async fn list_invitations(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let invitations = InvitationRepository::find_outstanding(&pool, Utc::now())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to list invitations: {}", e)))?;
+
+  let visible: Vec<Invitation> = invitations
+    .into_iter()
+    .filter(|invitation| {
+      let (permission, scope) = authority(invitation.role, invitation.faculty.as_deref());
+      staff.can(permission, scope)
+    })
+    .collect();
+  Ok(HttpResponse::Ok().json(visible))
+}
+
+/// Loads an invitation and checks that the caller may manage it.
+async fn find_managed(staff: &StaffAccess, pool: &PgPool, id: &Uuid) -> Result<Invitation, actix_web::Error> {
+  let invitation = InvitationRepository::find_by_id(pool, id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Invitation lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Invitation not found"))?;
+  let (permission, scope) = authority(invitation.role, invitation.faculty.as_deref());
+  staff.require(permission, scope)?;
+  Ok(invitation)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/invitations/{id}/resend",
+    params(
+        ("id", Path, description = "Invitation ID")
+    ),
+    responses(
+        (status = 200, description = "Link emailed again", body = Invitation),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller may not manage this invitation", body = String),
+        (status = 404, description = "Invitation not found", body = String),
+        (status = 409, description = "Invitation was used, revoked or has expired", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff", "verify_students"]))
+)]
+pub async fn resend_invitation(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+  mailer: web::Data<dyn Mailer>,
+  config: web::Data<Config>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let invitation = find_managed(&staff, &pool, &path.into_inner()).await?;
+  let secret = invitation_secret(&config)?;
+  let now = Utc::now();
+  if !invitation.is_outstanding(now) {
+    return Err(actix_web::error::ErrorConflict("Invitation was used, revoked or has expired"));
+  }
+
+  send_invitation(mailer.get_ref(), &config, &invitation, &sign_invitation(secret, &invitation))
+    .await
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+  let invitation = InvitationRepository::mark_sent(&pool, &invitation.id, now)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to update invitation: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(invitation))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/invitations/{id}",
+    params(
+        ("id", Path, description = "Invitation ID")
+    ),
+    responses(
+        (status = 200, description = "Invitation revoked; its link no longer works", body = Invitation),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller may not manage this invitation", body = String),
+        (status = 404, description = "Invitation not found", body = String),
+        (status = 409, description = "Invitation was already used or revoked", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff", "verify_students"]))
+)]
+pub async fn revoke_invitation(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let invitation = find_managed(&staff, &pool, &path.into_inner()).await?;
+
+  let invitation = InvitationRepository::revoke(&pool, &invitation.id, Utc::now())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to revoke invitation: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorConflict("Invitation was already used or revoked"))?;
+
+  Ok(HttpResponse::Ok().json(invitation))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/redeem-invitation",
+    request_body = RedeemInvitationRequest,
+    responses(
+        (status = 201, description = "Account registered, verified and given the invited role", body = User),
+        (status = 400, description = "Link is invalid, used, revoked or expired, or the profile is missing", body = String),
+        (status = 409, description = "A user with the invited email already exists", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn redeem_invitation(
+  req: web::Json<RedeemInvitationRequest>,
+  pool: web::Data<PgPool>,
+  config: web::Data<Config>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let invalid = || actix_web::error::ErrorBadRequest("Invalid, used or expired invitation");
+  let secret = invitation_secret(&config)?;
+  let now = Utc::now();
+  let signed = verify_invitation(secret, &req.token, now).ok_or_else(invalid)?;
+
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let password_hash = hash_password(&req.password)
+    .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to hash password"))?;
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+
+  let invitation = InvitationRepository::find_by_id(&mut *tx, &signed.id)
+    .await
+    .map_err(internal("Invitation lookup failed"))?
+    .filter(|invitation| invitation.expires_at.timestamp() == signed.expires_at && invitation.is_outstanding(now))
+    .ok_or_else(invalid)?;
+
+  let user = UserRepository::create(&mut *tx, &invitation.email, &password_hash, invitation.role, UserStatus::Verified)
+    .await
+    .map_err(|_| actix_web::error::ErrorConflict("A user with this email already exists"))?;
+  // The link reached the invitee through this address.
+  let user = UserRepository::confirm_email(&mut *tx, &user.id, now)
+    .await
+    .map_err(internal("Failed to confirm email"))?;
+
+  match (invitation.role, invitation.staff_role) {
+    (UserRole::Admin, Some(role)) => {
+      RoleAssignmentRepository::create(
+        &mut *tx,
+        &RoleAssignment {
+          id: Uuid::new_v4(),
+          user_id: user.id,
+          role,
+          building: invitation.building.clone(),
+          faculty: invitation.faculty.clone(),
+          created_at: now,
+        },
+      )
+      .await
+      .map_err(internal("Failed to grant role"))?;
+    }
+    _ => {
+      let profile = req
+        .into_inner()
+        .profile
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("A student invitation needs profile details"))?;
+      let faculty = match (invitation.faculty.as_deref(), profile.faculty.as_deref()) {
+        (Some(invited), Some(given)) if !invited.eq_ignore_ascii_case(given.trim()) => {
+          return Err(actix_web::error::ErrorBadRequest(format!(
+            "The invitation is for the faculty {}",
+            invited
+          )))
+        }
+        (Some(faculty), _) | (None, Some(faculty)) => faculty.to_string(),
+        (None, None) => return Err(actix_web::error::ErrorBadRequest("Faculty is required")),
+      };
+      PostgresStudentProfileRepository
+        .create(
+          &mut *tx,
+          &user.id,
+          &faculty,
+          profile.course,
+          profile.gender,
+          profile.age,
+          profile.wake_hours,
+          profile.hobbies,
+          profile.mbti,
+        )
+        .await
+        .map_err(internal("Failed to create profile"))?;
+    }
+  }
+
+  let redeemed = InvitationRepository::redeem(&mut *tx, &invitation.id, &user.id, now)
+    .await
+    .map_err(internal("Failed to redeem invitation"))?;
+  if !redeemed {
+    return Err(invalid());
+  }
+
+  tx.commit().await.map_err(internal("Failed to commit registration"))?;
+  Ok(HttpResponse::Created().json(user))
+}
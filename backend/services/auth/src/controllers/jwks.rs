@@ -0,0 +1,13 @@
+use actix_web::{web, HttpResponse};
+use dormmatch_common::utils::jwt::JwtKeys;
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    responses(
+        (status = 200, description = "Public keys access tokens are signed with, by kid; empty when tokens use a shared secret", body = Object)
+    )
+)]
+pub async fn get_jwks(keys: web::Data<JwtKeys>) -> HttpResponse {
+  HttpResponse::Ok().json(keys.jwks())
+}
@@ -0,0 +1,46 @@
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  middleware::role::StaffAccess,
+  models::role_assignment::{Permission, Scope},
+  repositories::user::UserRepository,
+};
+use redis::Client;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::lockout::{clear, LoginSubject};
+
+#[utoipa::path(
+    delete,
+    path = "/auth/lockouts/{user_id}",
+    params(
+        ("user_id", Path, description = "Locked out user ID")
+    ),
+    responses(
+        (status = 204, description = "Failed login attempts forgotten and the account unlocked"),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the unlock_accounts permission", body = String),
+        (status = 404, description = "User not found", body = String)
+    ),
+    security(("bearerAuth" = ["unlock_accounts"]))
+)]
+pub async fn unlock_account(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+  redis: web::Data<Client>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::UnlockAccounts, Scope::global())?;
+
+  let user = UserRepository::find_by_id(&pool, &path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("User not found"))?;
+
+  // Address locks are left to expire: they may cover other accounts too.
+  clear(&redis, &LoginSubject::Email(&user.email))
+    .await
+    .map_err(|_| actix_web::error::ErrorInternalServerError("Session store error"))?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
@@ -1,2 +1,11 @@
+pub mod admin_applications;
 pub mod auth;
+pub mod email;
+pub mod invitations;
+pub mod jwks;
+pub mod lockout;
+pub mod password;
+pub mod roster;
+pub mod staff;
+pub mod two_factor;
 pub mod verify;
@@ -0,0 +1,111 @@
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  config::env::Config,
+  mail::Mailer,
+  models::email_token::EmailTokenPurpose,
+  repositories::{email_token::EmailTokenRepository, user::UserRepository},
+  utils::{crypto::hash_password, revocation::revoke_user_tokens},
+};
+use redis::Client;
+use serde::Deserialize;
+use sqlx::{types::chrono::Utc, PgPool};
+use utoipa::ToSchema;
+
+use crate::services::{
+  auth::{hash_token, revoke_user_refresh_tokens},
+  password_reset::send_password_reset,
+};
+
+#[derive(Deserialize, ToSchema)]
+pub struct ForgotPasswordRequest {
+  email: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+  /// Token from the reset link.
+  token: String,
+  password: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/forgot-password",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "A reset link is on its way if the account exists", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn forgot_password(
+  req: web::Json<ForgotPasswordRequest>,
+  pool: web::Data<PgPool>,
+  mailer: web::Data<dyn Mailer>,
+  config: web::Data<Config>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let user = UserRepository::find_by_email(&pool, &req.email)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?;
+
+  // The mail goes out in the background so that the response, and how long
+  // it takes, is the same whether or not the account exists.
+  if let Some(user) = user {
+    actix_web::rt::spawn(async move {
+      if let Err(e) = send_password_reset(&pool, mailer.get_ref(), &config, &user).await {
+        tracing::warn!("Password reset email to {} failed: {}", user.email, e);
+      }
+    });
+  }
+
+  Ok(HttpResponse::Accepted().body("If the account exists, a reset link has been sent"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/reset-password",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 204, description = "Password changed and every session of the user revoked"),
+        (status = 400, description = "Token is unknown, used or expired", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn reset_password(
+  req: web::Json<ResetPasswordRequest>,
+  pool: web::Data<PgPool>,
+  redis: web::Data<Client>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let password_hash = hash_password(&req.password)
+    .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to hash password"))?;
+  let now = Utc::now();
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+
+  let token = EmailTokenRepository::consume(&mut *tx, &hash_token(&req.token), EmailTokenPurpose::ResetPassword, now)
+    .await
+    .map_err(internal("Token lookup failed"))?
+    .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid or expired reset token"))?;
+  UserRepository::update_password(&mut *tx, &token.user_id, &password_hash)
+    .await
+    .map_err(internal("Failed to update password"))?;
+  // Following the link proves the user owns the address.
+  UserRepository::confirm_email(&mut *tx, &token.user_id, now)
+    .await
+    .map_err(internal("Failed to confirm email"))?;
+
+  // Sessions go first: if revoking fails the old password stays in place
+  // rather than a new one coexisting with sessions that should be gone.
+  let user_id = token.user_id.to_string();
+  let revoked = async {
+    revoke_user_tokens(&redis, &user_id).await?;
+    revoke_user_refresh_tokens(&redis, &user_id).await
+  };
+  revoked
+    .await
+    .map_err(|_| actix_web::error::ErrorInternalServerError("Session store error"))?;
+
+  tx.commit().await.map_err(internal("Failed to commit password reset"))?;
+  Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,261 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  middleware::role::StaffAccess,
+  models::{
+    role_assignment::{Permission, Scope},
+    roster::{RosterChange, RosterChangeKind, RosterEntry, RosterFlag, RosterImport},
+  },
+  repositories::roster::RosterRepository,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::Utc, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::services::roster::{diff_roster, parse_roster, RosterRowError};
+
+#[derive(Serialize, ToSchema)]
+pub struct RosterImportReport {
+  #[serde(flatten)]
+  import: RosterImport,
+  added: usize,
+  removed: usize,
+  changed: usize,
+  /// Users newly flagged because they dropped off the roster.
+  flagged: Vec<RosterFlag>,
+  /// Rows that were skipped, with the reason.
+  errors: Vec<RosterRowError>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResolveFlagRequest {
+  /// What was decided, e.g. that the student moved out or was re-enrolled.
+  resolution: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/roster/import",
+    request_body(content = String, content_type = "text/csv", description = "Roster with student_id, email, faculty and course columns"),
+    responses(
+        (status = 200, description = "Roster imported; malformed rows are listed in errors", body = RosterImportReport),
+        (status = 400, description = "File has no usable header row", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the verify_students permission", body = String)
+    ),
+    security(("bearerAuth" = ["verify_students"]))
+)]
+pub async fn import_roster(
+  staff: StaffAccess,
+  body: web::Bytes,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::VerifyStudents, Scope::global())?;
+
+  let (rows, errors) = parse_roster(&body).map_err(actix_web::error::ErrorBadRequest)?;
+
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let now = Utc::now();
+  let import = RosterImport {
+    id: Uuid::new_v4(),
+    imported_by: staff.claims.user_id(),
+    imported_at: now,
+    accepted_rows: rows.len() as i32,
+    rejected_rows: errors.len() as i32,
+  };
+
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+  RosterRepository::lock(&mut *tx)
+    .await
+    .map_err(internal("Failed to lock roster"))?;
+  let previous = RosterRepository::find_all(&mut *tx)
+    .await
+    .map_err(internal("Roster lookup failed"))?;
+
+  let kept: HashSet<&str> = errors.iter().filter_map(|e| e.student_id.as_deref()).collect();
+  let changes = diff_roster(import.id, &previous, &rows, &kept);
+  let removed: Vec<String> = changes
+    .iter()
+    .filter(|c| c.kind == RosterChangeKind::Removed)
+    .map(|c| c.student_id.clone())
+    .collect();
+  let entries: Vec<RosterEntry> = rows
+    .into_iter()
+    .map(|row| RosterEntry {
+      student_id: row.student_id,
+      email: row.email,
+      faculty: row.faculty,
+      course: row.course,
+      import_id: import.id,
+      updated_at: now,
+    })
+    .collect();
+
+  let import = RosterRepository::create_import(&mut *tx, &import)
+    .await
+    .map_err(internal("Failed to record import"))?;
+  let changes = RosterRepository::record_changes(&mut *tx, &import.id, &changes)
+    .await
+    .map_err(internal("Failed to record roster changes"))?;
+  RosterRepository::delete_entries(&mut *tx, &removed)
+    .await
+    .map_err(internal("Failed to drop removed students"))?;
+  RosterRepository::upsert_entries(&mut tx, &entries)
+    .await
+    .map_err(internal("Failed to store roster"))?;
+  RosterRepository::update_profiles(&mut *tx, &import.id, now)
+    .await
+    .map_err(internal("Failed to update profiles"))?;
+  RosterRepository::unflag_added_users(&mut *tx, &import.id)
+    .await
+    .map_err(internal("Failed to clear flags"))?;
+  let flagged = RosterRepository::flag_removed_users(&mut *tx, &import.id, now)
+    .await
+    .map_err(internal("Failed to flag removed students"))?;
+  tx.commit().await.map_err(internal("Failed to commit import"))?;
+
+  let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+  Ok(HttpResponse::Ok().json(RosterImportReport {
+    added: count(RosterChangeKind::Added),
+    removed: count(RosterChangeKind::Removed),
+    changed: count(RosterChangeKind::Changed),
+    import,
+    flagged,
+    errors,
+  }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/roster/imports",
+    responses(
+        (status = 200, description = "Roster imports, newest first", body = [RosterImport]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the verify_students permission", body = String)
+    ),
+    security(("bearerAuth" = ["verify_students"]))
+)]
+pub async fn list_imports(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::VerifyStudents, Scope::global())?;
+
+  let imports = RosterRepository::find_imports(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to list imports: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(imports))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/roster/imports/{id}/changes",
+    params(
+        ("id", Path, description = "Roster import ID")
+    ),
+    responses(
+        (status = 200, description = "What the import added, removed and changed", body = [RosterChange]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the verify_students permission", body = String)
+    ),
+    security(("bearerAuth" = ["verify_students"]))
+)]
+pub async fn get_import_changes(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::VerifyStudents, Scope::global())?;
+
+  let changes = RosterRepository::find_changes(&pool, &path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get changes: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(changes))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/roster/flags",
+    responses(
+        (status = 200, description = "Unresolved flags of users who dropped off the roster", body = [RosterFlag]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the verify_students permission", body = String)
+    ),
+    security(("bearerAuth" = ["verify_students"]))
+)]
+pub async fn list_flags(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::VerifyStudents, Scope::global())?;
+
+  let flags = RosterRepository::find_flags(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to list flags: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(flags))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/roster/review-queue",
+    responses(
+        (status = 200, description = "Flagged users who still live in a room", body = [RosterFlag]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the verify_students permission", body = String)
+    ),
+    security(("bearerAuth" = ["verify_students"]))
+)]
+pub async fn get_review_queue(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::VerifyStudents, Scope::global())?;
+
+  let queue = RosterRepository::find_review_queue(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get review queue: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(queue))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/roster/flags/{user_id}/resolve",
+    params(
+        ("user_id", Path, description = "Flagged user ID")
+    ),
+    request_body = ResolveFlagRequest,
+    responses(
+        (status = 200, description = "Flag resolved", body = RosterFlag),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the verify_students permission", body = String),
+        (status = 404, description = "User has no unresolved flag", body = String)
+    ),
+    security(("bearerAuth" = ["verify_students"]))
+)]
+pub async fn resolve_flag(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  req: web::Json<ResolveFlagRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::VerifyStudents, Scope::global())?;
+  let resolved_by = staff
+    .claims
+    .user_id()
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+
+  let flag = RosterRepository::resolve_flag(&pool, &path.into_inner(), &resolved_by, &req.resolution, Utc::now())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to resolve flag: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("User has no unresolved flag"))?;
+
+  Ok(HttpResponse::Ok().json(flag))
+}
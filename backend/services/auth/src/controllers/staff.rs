@@ -0,0 +1,132 @@
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  middleware::role::StaffAccess,
+  models::role_assignment::{Permission, RoleAssignment, Scope, StaffRole},
+  repositories::{role_assignment::RoleAssignmentRepository, user::UserRepository},
+};
+use serde::Deserialize;
+use sqlx::{types::chrono::Utc, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Deserialize, ToSchema)]
+pub struct RoleAssignmentRequest {
+  role: StaffRole,
+  /// Required for building managers.
+  building: Option<String>,
+  /// Required for faculty coordinators.
+  faculty: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/staff/{user_id}/roles",
+    params(
+        ("user_id", Path, description = "Staff user ID")
+    ),
+    responses(
+        (status = 200, description = "Roles held by the user", body = [RoleAssignment]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_staff permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff"]))
+)]
+pub async fn list_roles(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageStaff, Scope::global())?;
+
+  let roles = RoleAssignmentRepository::find_by_user_id(&pool, &path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Role lookup failed: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(roles))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/staff/{user_id}/roles",
+    params(
+        ("user_id", Path, description = "Staff user ID")
+    ),
+    request_body = RoleAssignmentRequest,
+    responses(
+        (status = 201, description = "Role granted", body = RoleAssignment),
+        (status = 400, description = "Scope does not fit the role", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_staff permission", body = String),
+        (status = 404, description = "User not found", body = String),
+        (status = 409, description = "User is not an admin account", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff"]))
+)]
+pub async fn grant_role(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  req: web::Json<RoleAssignmentRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageStaff, Scope::global())?;
+  req
+    .role
+    .check_scope(req.building.as_deref(), req.faculty.as_deref())
+    .map_err(actix_web::error::ErrorBadRequest)?;
+
+  let user = UserRepository::find_by_id(&pool, &path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("User not found"))?;
+  if user.role != "admin" {
+    return Err(actix_web::error::ErrorConflict("Staff roles can only be granted to admin accounts"));
+  }
+
+  let req = req.into_inner();
+  let assignment = RoleAssignmentRepository::create(
+    pool.get_ref(),
+    &RoleAssignment {
+      id: Uuid::new_v4(),
+      user_id: user.id,
+      role: req.role,
+      building: req.building,
+      faculty: req.faculty,
+      created_at: Utc::now(),
+    },
+  )
+  .await
+  .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to grant role: {}", e)))?;
+
+  Ok(HttpResponse::Created().json(assignment))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/staff/{user_id}/roles/{id}",
+    params(
+        ("user_id", Path, description = "Staff user ID"),
+        ("id", Path, description = "Role assignment ID")
+    ),
+    responses(
+        (status = 200, description = "Role revoked", body = RoleAssignment),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_staff permission", body = String),
+        (status = 404, description = "Role assignment not found", body = String)
+    ),
+    security(("bearerAuth" = ["manage_staff"]))
+)]
+pub async fn revoke_role(
+  staff: StaffAccess,
+  path: web::Path<(Uuid, Uuid)>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageStaff, Scope::global())?;
+
+  let (user_id, id) = path.into_inner();
+  let assignment = RoleAssignmentRepository::delete(&pool, &user_id, &id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to revoke role: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Role assignment not found"))?;
+
+  Ok(HttpResponse::Ok().json(assignment))
+}
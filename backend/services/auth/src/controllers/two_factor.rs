@@ -0,0 +1,317 @@
+use actix_web::{http::header, web, HttpResponse};
+use dormmatch_common::{
+  config::env::Config,
+  middleware::role::{Admin, RoleGuard},
+  repositories::{two_factor::TwoFactorRepository, user::UserRepository},
+  utils::{jwt::JwtKeys, revocation::revoke_user_tokens},
+};
+use redis::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::Utc, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::controllers::auth::issue_tokens;
+use crate::services::{
+  auth::revoke_user_refresh_tokens,
+  lockout::{clear, record_failure, retry_after, LoginSubject},
+  totp::{encode_secret, new_secret, otpauth_url, verify_code},
+  two_factor::{
+    challenge_user, check_second_factor, fail_challenge, finish_challenge, hash_recovery_code,
+    new_recovery_codes,
+  },
+};
+
+/// Issuer shown next to the account in authenticator apps.
+const TOTP_ISSUER: &str = "DormMatch";
+
+#[derive(Deserialize, ToSchema)]
+pub struct TwoFactorLoginRequest {
+  /// Token from the 202 answer of `/auth/login`.
+  two_factor_token: String,
+  /// Six-digit TOTP code or an unused recovery code.
+  code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TwoFactorCodeRequest {
+  code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TotpEnrollment {
+  /// Base32 secret for manual entry.
+  secret: String,
+  /// `otpauth://` URI, usually shown as a QR code.
+  otpauth_url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecoveryCodes {
+  /// Each code replaces a TOTP code once. They are not shown again.
+  recovery_codes: Vec<String>,
+}
+
+fn caller_id(admin: &RoleGuard<Admin>) -> Result<Uuid, actix_web::Error> {
+  admin
+    .claims
+    .user_id()
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login/2fa",
+    request_body = TwoFactorLoginRequest,
+    responses(
+        (status = 200, description = "Second factor accepted", body = crate::controllers::auth::LoginResponse),
+        (status = 401, description = "Challenge expired or code invalid; after five wrong codes the password is asked again. Wrong codes also count as failed logins of the account, and Retry-After is set once they are throttled", body = String),
+        (status = 429, description = "Too many failed attempts for the account; see Retry-After", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub async fn login_two_factor(
+  req: web::Json<TwoFactorLoginRequest>,
+  pool: web::Data<PgPool>,
+  redis: web::Data<Client>,
+  keys: web::Data<JwtKeys>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let session_error = |_| actix_web::error::ErrorInternalServerError("Session store error");
+  let expired = || actix_web::error::ErrorUnauthorized("Two-factor challenge expired; log in again");
+
+  let user_id = challenge_user(&redis, &req.two_factor_token)
+    .await
+    .map_err(session_error)?
+    .and_then(|id| Uuid::parse_str(&id).ok())
+    .ok_or_else(expired)?;
+  let credential = TwoFactorRepository::find(&pool, &user_id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Credential lookup failed: {}", e)))?
+    .filter(|credential| credential.confirmed_at.is_some())
+    .ok_or_else(expired)?;
+  let user = UserRepository::find_by_id(&pool, &user_id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?
+    .ok_or_else(expired)?;
+
+  // Wrong codes count against the account like wrong passwords, so opening
+  // fresh challenges does not buy more guesses.
+  let subject = [LoginSubject::Email(&user.email)];
+  if let Some(seconds) = retry_after(&redis, &subject).await.map_err(session_error)? {
+    return Ok(
+      HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, seconds.to_string()))
+        .body("Too many failed login attempts"),
+    );
+  }
+
+  let accepted = check_second_factor(&pool, &credential, &req.code, Utc::now())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Code check failed: {}", e)))?;
+  if !accepted {
+    fail_challenge(&redis, &req.two_factor_token).await.map_err(session_error)?;
+    let mut response = HttpResponse::Unauthorized();
+    if let Some(seconds) = record_failure(&redis, &subject).await.map_err(session_error)? {
+      response.insert_header((header::RETRY_AFTER, seconds.to_string()));
+    }
+    return Ok(response.body("Invalid code"));
+  }
+  if !finish_challenge(&redis, &req.two_factor_token).await.map_err(session_error)? {
+    return Err(expired());
+  }
+
+  clear(&redis, &subject[0]).await.map_err(session_error)?;
+  Ok(issue_tokens(&user, &redis, &keys, true).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/enroll",
+    responses(
+        (status = 200, description = "New secret; enrollment finishes at /auth/2fa/confirm", body = TotpEnrollment),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Admin role required", body = String),
+        (status = 409, description = "Two-factor authentication is already enabled", body = String)
+    ),
+    security(("bearerAuth" = ["admin"]))
+)]
+pub async fn enroll_two_factor(
+  admin: RoleGuard<Admin>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let user = UserRepository::find_by_id(&pool, &caller_id(&admin)?)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("User not found"))?;
+
+  let secret = new_secret();
+  TwoFactorRepository::start_enrollment(&pool, &user.id, &secret, Utc::now())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to store secret: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorConflict("Two-factor authentication is already enabled"))?;
+
+  Ok(HttpResponse::Ok().json(TotpEnrollment {
+    secret: encode_secret(&secret),
+    otpauth_url: otpauth_url(&secret, TOTP_ISSUER, &user.email),
+  }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/confirm",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "Two-factor authentication enabled and every session revoked; log in again", body = RecoveryCodes),
+        (status = 400, description = "Invalid code", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Admin role required", body = String),
+        (status = 409, description = "No enrollment in progress", body = String)
+    ),
+    security(("bearerAuth" = ["admin"]))
+)]
+pub async fn confirm_two_factor(
+  admin: RoleGuard<Admin>,
+  req: web::Json<TwoFactorCodeRequest>,
+  pool: web::Data<PgPool>,
+  redis: web::Data<Client>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let user_id = caller_id(&admin)?;
+  let credential = TwoFactorRepository::find(&pool, &user_id)
+    .await
+    .map_err(internal("Credential lookup failed"))?
+    .filter(|credential| credential.confirmed_at.is_none())
+    .ok_or_else(|| actix_web::error::ErrorConflict("No two-factor enrollment in progress"))?;
+
+  let now = Utc::now();
+  let step = verify_code(&credential.secret, &req.code, now.timestamp() as u64)
+    .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid code"))?;
+  let codes = new_recovery_codes();
+  let hashes: Vec<String> = codes.iter().map(|code| hash_recovery_code(code)).collect();
+
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+  if !TwoFactorRepository::use_step(&mut *tx, &user_id, step as i64)
+    .await
+    .map_err(internal("Failed to record code"))?
+  {
+    return Err(actix_web::error::ErrorBadRequest("Code was already used"));
+  }
+  TwoFactorRepository::confirm(&mut *tx, &user_id, now)
+    .await
+    .map_err(internal("Failed to enable two-factor authentication"))?;
+  TwoFactorRepository::replace_recovery_codes(&mut tx, &user_id, &hashes)
+    .await
+    .map_err(internal("Failed to store recovery codes"))?;
+
+  tx.commit().await.map_err(internal("Failed to commit enrollment"))?;
+
+  // Sessions opened with the password alone would otherwise carry on.
+  let user_id = user_id.to_string();
+  let revoked = async {
+    revoke_user_tokens(&redis, &user_id).await?;
+    revoke_user_refresh_tokens(&redis, &user_id).await
+  };
+  revoked
+    .await
+    .map_err(|_| actix_web::error::ErrorInternalServerError("Session store error"))?;
+  Ok(HttpResponse::Ok().json(RecoveryCodes { recovery_codes: codes }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/recovery-codes",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "New recovery codes; the old ones stop working", body = RecoveryCodes),
+        (status = 400, description = "Invalid TOTP code", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Admin role required", body = String),
+        (status = 409, description = "Two-factor authentication is not enabled", body = String)
+    ),
+    security(("bearerAuth" = ["admin"]))
+)]
+pub async fn regenerate_recovery_codes(
+  admin: RoleGuard<Admin>,
+  req: web::Json<TwoFactorCodeRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let user_id = caller_id(&admin)?;
+  let credential = TwoFactorRepository::find(&pool, &user_id)
+    .await
+    .map_err(internal("Credential lookup failed"))?
+    .filter(|credential| credential.confirmed_at.is_some())
+    .ok_or_else(|| actix_web::error::ErrorConflict("Two-factor authentication is not enabled"))?;
+
+  // Only the authenticator itself will do here, not a recovery code.
+  let step = verify_code(&credential.secret, &req.code, Utc::now().timestamp() as u64)
+    .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid code"))?;
+  let codes = new_recovery_codes();
+  let hashes: Vec<String> = codes.iter().map(|code| hash_recovery_code(code)).collect();
+
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+  if !TwoFactorRepository::use_step(&mut *tx, &user_id, step as i64)
+    .await
+    .map_err(internal("Failed to record code"))?
+  {
+    return Err(actix_web::error::ErrorBadRequest("Code was already used"));
+  }
+  TwoFactorRepository::replace_recovery_codes(&mut tx, &user_id, &hashes)
+    .await
+    .map_err(internal("Failed to store recovery codes"))?;
+  tx.commit().await.map_err(internal("Failed to commit recovery codes"))?;
+
+  Ok(HttpResponse::Ok().json(RecoveryCodes { recovery_codes: codes }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/2fa",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Invalid code", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Admin role required", body = String),
+        (status = 409, description = "Not enabled, or mandatory for admins", body = String)
+    ),
+    security(("bearerAuth" = ["admin"]))
+)]
+pub async fn disable_two_factor(
+  admin: RoleGuard<Admin>,
+  req: web::Json<TwoFactorCodeRequest>,
+  pool: web::Data<PgPool>,
+  config: web::Data<Config>,
+) -> Result<HttpResponse, actix_web::Error> {
+  if config.require_admin_2fa {
+    return Err(actix_web::error::ErrorConflict("Two-factor authentication is mandatory for admins"));
+  }
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let user_id = caller_id(&admin)?;
+  let credential = TwoFactorRepository::find(&pool, &user_id)
+    .await
+    .map_err(internal("Credential lookup failed"))?
+    .filter(|credential| credential.confirmed_at.is_some())
+    .ok_or_else(|| actix_web::error::ErrorConflict("Two-factor authentication is not enabled"))?;
+
+  if !check_second_factor(&pool, &credential, &req.code, Utc::now())
+    .await
+    .map_err(internal("Code check failed"))?
+  {
+    return Err(actix_web::error::ErrorBadRequest("Invalid code"));
+  }
+
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+  TwoFactorRepository::delete(&mut tx, &user_id)
+    .await
+    .map_err(internal("Failed to disable two-factor authentication"))?;
+  tx.commit().await.map_err(internal("Failed to commit"))?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
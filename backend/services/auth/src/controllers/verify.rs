@@ -1,5 +1,15 @@
 use actix_web::{web, HttpResponse, Responder};
-use dormmatch_common::{models::user::UserStatus, repositories::user::UserRepository};
+use dormmatch_common::{
+  middleware::role::StaffAccess,
+  models::{
+    role_assignment::{Permission, Scope},
+    user::UserStatus,
+  },
+  repositories::{
+    profile::{PostgresStudentProfileRepository, StudentProfileRepository},
+    user::UserRepository,
+  },
+};
 use serde::Deserialize;
 use sqlx::PgPool;
 use utoipa::ToSchema;
@@ -19,13 +29,27 @@ pub struct VerifyStudentRequest {
     request_body = VerifyStudentRequest,
     responses(
         (status = 200, description = "User status updated", body = User),
-        (status = 404, description = "User not found", body = String)
-    )
+        (status = 404, description = "User not found", body = String),
+        (status = 500, description = "Profile lookup failed", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the verify_students permission", body = String)
+    ),
+    security(("bearerAuth" = ["verify_students"]))
 )]
 pub async fn verify_student(
+  staff: StaffAccess,
   req: web::Json<VerifyStudentRequest>,
   pool: web::Data<PgPool>,
 ) -> impl Responder {
+  // Coordinators are limited to their faculty, so look up the student's.
+  let faculty = match PostgresStudentProfileRepository::find_by_user_id(pool.get_ref(), &req.user_id).await {
+    Ok(profile) => profile.map(|profile| profile.faculty),
+    Err(e) => return HttpResponse::InternalServerError().body(format!("Profile lookup failed: {}", e)),
+  };
+  if let Err(e) = staff.require(Permission::VerifyStudents, Scope::faculty(faculty.as_deref())) {
+    return HttpResponse::from_error(e);
+  }
+
   let status = if req.is_verified {
     UserStatus::Verified
   } else {
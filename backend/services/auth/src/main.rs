@@ -1,15 +1,22 @@
 use actix_web::{web, App, HttpServer};
-use dormmatch_common::config::env::Config;
-use sqlx::PgPool;
-use tracing_subscriber;
+use actix_web_httpauth::middleware::HttpAuthentication;
+use dormmatch_common::{
+  config::env::Config, mail, middleware::jwt::jwt_middleware, utils::jwt::JwtKeys,
+};
 
 mod config;
 mod controllers;
-mod middleware;
 mod openapi;
 mod services;
 
+/// Upper bound on an uploaded roster; a whole university fits comfortably.
+const ROSTER_MAX_BYTES: usize = 16 * 1024 * 1024;
+
 fn configure_routes(cfg: &mut web::ServiceConfig) {
+  cfg.route(
+    "/.well-known/jwks.json",
+    web::get().to(controllers::jwks::get_jwks),
+  );
   cfg.service(
     web::scope("/auth")
       .route(
@@ -18,8 +25,119 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
       )
       .route("/login", web::post().to(controllers::auth::login))
       .route(
-        "/verify",
-        web::post().to(controllers::verify::verify_student),
+        "/login/2fa",
+        web::post().to(controllers::two_factor::login_two_factor),
+      )
+      .route("/refresh", web::post().to(controllers::auth::refresh))
+      .route(
+        "/confirm-email",
+        web::post().to(controllers::email::confirm_email),
+      )
+      .route(
+        "/resend-confirmation",
+        web::post().to(controllers::email::resend_confirmation),
+      )
+      .route(
+        "/forgot-password",
+        web::post().to(controllers::password::forgot_password),
+      )
+      .route(
+        "/reset-password",
+        web::post().to(controllers::password::reset_password),
+      )
+      .service(
+        web::resource("/admin-applications")
+          .route(web::post().to(controllers::admin_applications::apply_admin))
+          .route(
+            web::get()
+              .to(controllers::admin_applications::list_admin_applications)
+              .wrap(HttpAuthentication::bearer(jwt_middleware)),
+          ),
+      )
+      .service(
+        web::scope("/admin-applications/{id}")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route(
+            "/approve",
+            web::post().to(controllers::admin_applications::approve_admin_application),
+          )
+          .route(
+            "/reject",
+            web::post().to(controllers::admin_applications::reject_admin_application),
+          ),
+      )
+      .route(
+        "/redeem-invitation",
+        web::post().to(controllers::invitations::redeem_invitation),
+      )
+      .service(
+        web::scope("/invitations")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route("", web::post().to(controllers::invitations::create_invitation))
+          .route("", web::get().to(controllers::invitations::list_invitations))
+          .route(
+            "/{id}/resend",
+            web::post().to(controllers::invitations::resend_invitation),
+          )
+          .route("/{id}", web::delete().to(controllers::invitations::revoke_invitation)),
+      )
+      .service(
+        web::scope("/2fa")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route("", web::delete().to(controllers::two_factor::disable_two_factor))
+          .route("/enroll", web::post().to(controllers::two_factor::enroll_two_factor))
+          .route("/confirm", web::post().to(controllers::two_factor::confirm_two_factor))
+          .route(
+            "/recovery-codes",
+            web::post().to(controllers::two_factor::regenerate_recovery_codes),
+          ),
+      )
+      .service(
+        web::resource("/logout")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route(web::post().to(controllers::auth::logout)),
+      )
+      .service(
+        web::resource("/logout-all")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route(web::post().to(controllers::auth::logout_all)),
+      )
+      .service(
+        web::resource("/verify")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route(web::post().to(controllers::verify::verify_student)),
+      )
+      .service(
+        web::scope("/roster")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .service(
+            web::resource("/import")
+              .app_data(web::PayloadConfig::new(ROSTER_MAX_BYTES))
+              .route(web::post().to(controllers::roster::import_roster)),
+          )
+          .route("/imports", web::get().to(controllers::roster::list_imports))
+          .route(
+            "/imports/{id}/changes",
+            web::get().to(controllers::roster::get_import_changes),
+          )
+          .route("/flags", web::get().to(controllers::roster::list_flags))
+          .route(
+            "/flags/{user_id}/resolve",
+            web::post().to(controllers::roster::resolve_flag),
+          )
+          .route("/review-queue", web::get().to(controllers::roster::get_review_queue)),
+      )
+      .service(
+        web::resource("/lockouts/{user_id}")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route(web::delete().to(controllers::lockout::unlock_account)),
+      )
+      .service(
+        web::scope("/staff/{user_id}/roles")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route("", web::get().to(controllers::staff::list_roles))
+          .route("", web::post().to(controllers::staff::grant_role))
+          .route("/{id}", web::delete().to(controllers::staff::revoke_role)),
       ),
   );
 
@@ -32,16 +150,21 @@ async fn main() -> std::io::Result<()> {
   let config = Config::from_env();
   let port_auth = config.port_auth; // Store port_auth before moving config
 
-  let pool = PgPool::connect(&config.database_url)
-    .await
-    .expect("Failed to connect to PostgreSQL");
+  let pool = config::db::init_db(&config).await;
+  let redis = redis::Client::open(config.redis_url.as_str()).expect("Invalid Redis URL");
+  let mailer = web::Data::from(mail::from_config(&config));
+  let keys = web::Data::new(JwtKeys::from_config(&config).expect("Invalid JWT key configuration"));
+  assert!(keys.can_sign(), "The auth service needs a signing key (JWT_SECRET or JWT_PRIVATE_KEY)");
 
   println!("Server started!");
 
   HttpServer::new(move || {
     App::new()
       .app_data(web::Data::new(pool.clone()))
+      .app_data(web::Data::new(redis.clone()))
       .app_data(web::Data::new(config.clone()))
+      .app_data(mailer.clone())
+      .app_data(keys.clone())
       .configure(configure_routes)
       .configure(openapi::configure_openapi)
   })
@@ -1,21 +1,73 @@
 use utoipa::{
-  openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+  openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
   Modify, OpenApi,
 };
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::controllers::{
-  auth::{LoginRequest, LoginResponse, RegisterStudentRequest},
+  admin_applications::{
+    AdminApplicationRequest, ApproveAdminApplicationRequest, ApprovedAdminApplication, ReviewAdminApplicationRequest,
+  },
+  auth::{
+    LoginRequest, LoginResponse, LogoutRequest, RefreshRequest, RegisterStudentRequest,
+    TwoFactorChallenge,
+  },
+  email::{ConfirmEmailRequest, ResendConfirmationRequest},
+  invitations::{CreateInvitationRequest, InvitedStudentProfile, RedeemInvitationRequest},
+  password::{ForgotPasswordRequest, ResetPasswordRequest},
+  roster::{ResolveFlagRequest, RosterImportReport},
+  staff::RoleAssignmentRequest,
+  two_factor::{RecoveryCodes, TotpEnrollment, TwoFactorCodeRequest, TwoFactorLoginRequest},
   verify::VerifyStudentRequest,
 };
-use dormmatch_common::models::{profile::StudentProfile, user::User};
+use crate::services::roster::RosterRowError;
+use dormmatch_common::models::{
+  admin_application::{AdminApplication, AdminApplicationStatus},
+  invitation::Invitation,
+  profile::StudentProfile,
+  role_assignment::{RoleAssignment, StaffRole},
+  roster::{RosterChange, RosterChangeKind, RosterFlag, RosterImport},
+  user::{User, UserRole},
+};
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         crate::controllers::auth::register_student,
+        crate::controllers::jwks::get_jwks,
         crate::controllers::auth::login,
+        crate::controllers::two_factor::login_two_factor,
+        crate::controllers::two_factor::enroll_two_factor,
+        crate::controllers::two_factor::confirm_two_factor,
+        crate::controllers::two_factor::regenerate_recovery_codes,
+        crate::controllers::two_factor::disable_two_factor,
+        crate::controllers::auth::refresh,
+        crate::controllers::email::confirm_email,
+        crate::controllers::email::resend_confirmation,
+        crate::controllers::password::forgot_password,
+        crate::controllers::password::reset_password,
+        crate::controllers::auth::logout,
+        crate::controllers::auth::logout_all,
         crate::controllers::verify::verify_student,
+        crate::controllers::roster::import_roster,
+        crate::controllers::roster::list_imports,
+        crate::controllers::roster::get_import_changes,
+        crate::controllers::roster::list_flags,
+        crate::controllers::roster::get_review_queue,
+        crate::controllers::roster::resolve_flag,
+        crate::controllers::staff::list_roles,
+        crate::controllers::staff::grant_role,
+        crate::controllers::staff::revoke_role,
+        crate::controllers::lockout::unlock_account,
+        crate::controllers::admin_applications::apply_admin,
+        crate::controllers::admin_applications::list_admin_applications,
+        crate::controllers::admin_applications::approve_admin_application,
+        crate::controllers::admin_applications::reject_admin_application,
+        crate::controllers::invitations::create_invitation,
+        crate::controllers::invitations::list_invitations,
+        crate::controllers::invitations::resend_invitation,
+        crate::controllers::invitations::revoke_invitation,
+        crate::controllers::invitations::redeem_invitation,
     ),
     components(
         schemas(
@@ -24,7 +76,39 @@ use dormmatch_common::models::{profile::StudentProfile, user::User};
             RegisterStudentRequest,
             LoginRequest,
             LoginResponse,
+            TwoFactorChallenge,
+            TwoFactorLoginRequest,
+            TwoFactorCodeRequest,
+            TotpEnrollment,
+            RecoveryCodes,
+            RefreshRequest,
+            ConfirmEmailRequest,
+            ResendConfirmationRequest,
+            ForgotPasswordRequest,
+            ResetPasswordRequest,
+            LogoutRequest,
             VerifyStudentRequest,
+            RosterImport,
+            RosterImportReport,
+            RosterRowError,
+            RosterChange,
+            RosterChangeKind,
+            RosterFlag,
+            ResolveFlagRequest,
+            RoleAssignment,
+            RoleAssignmentRequest,
+            StaffRole,
+            AdminApplication,
+            AdminApplicationStatus,
+            AdminApplicationRequest,
+            ReviewAdminApplicationRequest,
+            ApproveAdminApplicationRequest,
+            ApprovedAdminApplication,
+            Invitation,
+            CreateInvitationRequest,
+            InvitedStudentProfile,
+            RedeemInvitationRequest,
+            UserRole,
         )
     ),
     modifiers(&SecurityAddon),
@@ -49,7 +133,12 @@ impl Modify for SecurityAddon {
       .get_or_insert_with(utoipa::openapi::Components::new);
     components.add_security_scheme(
       "bearerAuth",
-      SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+      SecurityScheme::Http(
+        HttpBuilder::new()
+          .scheme(HttpAuthScheme::Bearer)
+          .bearer_format("JWT")
+          .build(),
+      ),
     );
   }
 }
@@ -1,22 +1,146 @@
 use actix_web::web;
-use redis::{AsyncCommands, Client};
+use rand::{rngs::OsRng, RngCore};
+use redis::{AsyncCommands, Client, Script};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
-pub async fn store_session(
+/// Lifetime of a refresh token (and of its family) in seconds.
+pub const REFRESH_TOKEN_TTL: u64 = 30 * 24 * 3600;
+
+/// Swaps the family's current token for a new one, but only if the presented
+/// token is still the current one. Otherwise the token has been used before,
+/// so the whole family is revoked.
+const ROTATE_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[4])
+  redis.call('SET', KEYS[2], ARGV[3], 'EX', ARGV[4])
+  return 1
+end
+redis.call('DEL', KEYS[1])
+return 0
+";
+
+pub enum Refresh {
+  /// The token was valid and has been replaced by `token`.
+  Rotated { user_id: String, token: String },
+  /// Unknown or expired token.
+  Invalid,
+  /// An already rotated token was replayed; its family is now revoked.
+  Reused,
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+  hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub(crate) fn new_token() -> String {
+  let mut bytes = [0u8; 32];
+  OsRng.fill_bytes(&mut bytes);
+  hex::encode(bytes)
+}
+
+fn token_key(hash: &str) -> String {
+  format!("refresh:{}", hash)
+}
+
+fn family_key(family: &str) -> String {
+  format!("refresh_family:{}", family)
+}
+
+fn user_families_key(user_id: &str) -> String {
+  format!("refresh_families:{}", user_id)
+}
+
+/// Starts a new token family for `user_id` and returns its first token.
+/// Only the token's hash is stored.
+pub async fn issue_refresh_token(
+  redis_client: &web::Data<Client>,
+  user_id: &str,
+) -> Result<String, redis::RedisError> {
+  let mut conn = redis_client.get_multiplexed_async_connection().await?;
+  let family = Uuid::new_v4().to_string();
+  let token = new_token();
+  let hash = hash_token(&token);
+
+  redis::pipe()
+    .atomic()
+    .set_ex(token_key(&hash), format!("{}:{}", user_id, family), REFRESH_TOKEN_TTL)
+    .set_ex(family_key(&family), &hash, REFRESH_TOKEN_TTL)
+    .sadd(user_families_key(user_id), &family)
+    .expire(user_families_key(user_id), REFRESH_TOKEN_TTL as i64)
+    .query_async::<_, ()>(&mut conn)
+    .await?;
+  Ok(token)
+}
+
+/// Exchanges a refresh token for a new one of the same family.
+pub async fn rotate_refresh_token(
+  redis_client: &web::Data<Client>,
+  token: &str,
+) -> Result<Refresh, redis::RedisError> {
+  let mut conn = redis_client.get_multiplexed_async_connection().await?;
+  let hash = hash_token(token);
+
+  let record: Option<String> = conn.get(token_key(&hash)).await?;
+  let Some((user_id, family)) = record.as_deref().and_then(|r| r.split_once(':')) else {
+    return Ok(Refresh::Invalid);
+  };
+
+  let next = new_token();
+  let next_hash = hash_token(&next);
+  let rotated: i32 = Script::new(ROTATE_SCRIPT)
+    .key(family_key(family))
+    .key(token_key(&next_hash))
+    .arg(&hash)
+    .arg(&next_hash)
+    .arg(format!("{}:{}", user_id, family))
+    .arg(REFRESH_TOKEN_TTL)
+    .invoke_async(&mut conn)
+    .await?;
+
+  if rotated == 1 {
+    Ok(Refresh::Rotated {
+      user_id: user_id.to_string(),
+      token: next,
+    })
+  } else {
+    Ok(Refresh::Reused)
+  }
+}
+
+/// Revokes the family of `token` if it belongs to `user_id`.
+pub async fn revoke_refresh_token(
   redis_client: &web::Data<Client>,
   user_id: &str,
   token: &str,
 ) -> Result<(), redis::RedisError> {
   let mut conn = redis_client.get_multiplexed_async_connection().await?;
-  conn
-    .set_ex::<_, _, ()>(&format!("session:{}", user_id), token, 3600)
-    .await?;
+  let record: Option<String> = conn.get(token_key(&hash_token(token))).await?;
+  if let Some((owner, family)) = record.as_deref().and_then(|r| r.split_once(':')) {
+    if owner == user_id {
+      redis::pipe()
+        .del(family_key(family))
+        .srem(user_families_key(user_id), family)
+        .query_async::<_, ()>(&mut conn)
+        .await?;
+    }
+  }
   Ok(())
 }
 
-pub async fn get_session(
+/// Revokes every refresh token family of the user.
+pub async fn revoke_user_refresh_tokens(
   redis_client: &web::Data<Client>,
   user_id: &str,
-) -> Result<Option<String>, redis::RedisError> {
+) -> Result<(), redis::RedisError> {
   let mut conn = redis_client.get_multiplexed_async_connection().await?;
-  conn.get(&format!("session:{}", user_id)).await
+  let families: Vec<String> = conn.smembers(user_families_key(user_id)).await?;
+  let mut pipe = redis::pipe();
+  for family in &families {
+    pipe.del(family_key(family));
+  }
+  pipe
+    .del(user_families_key(user_id))
+    .query_async::<_, ()>(&mut conn)
+    .await
 }
@@ -0,0 +1,52 @@
+use chrono::{Duration, Utc};
+use dormmatch_common::{
+  config::env::Config,
+  mail::{Email, Mailer},
+  models::{email_token::EmailTokenPurpose, user::User},
+  repositories::email_token::EmailTokenRepository,
+};
+use sqlx::PgPool;
+
+use crate::services::email_token::issue_email_token;
+
+/// How long a confirmation link stays valid, in hours.
+pub const CONFIRM_TOKEN_TTL_HOURS: i64 = 24;
+
+/// Minimum time between two confirmation emails to the same user, in seconds.
+pub const RESEND_COOLDOWN_SECS: i64 = 60;
+
+/// Issues a new confirmation token for `user` and emails the link. Links
+/// sent earlier stop working.
+pub async fn send_confirmation(
+  pool: &PgPool,
+  mailer: &dyn Mailer,
+  config: &Config,
+  user: &User,
+) -> Result<(), String> {
+  let token = issue_email_token(
+    pool,
+    &user.id,
+    EmailTokenPurpose::ConfirmEmail,
+    Duration::hours(CONFIRM_TOKEN_TTL_HOURS),
+  )
+  .await?;
+
+  mailer
+    .send(&Email {
+      to: user.email.clone(),
+      subject: "Confirm your DormMatch email".to_string(),
+      body: format!(
+        "Hello,\n\nPlease confirm your email address by opening this link:\n\n{}/confirm-email?token={}\n\nThe link expires in {} hours. If you did not sign up for DormMatch, ignore this email.\n",
+        config.app_url.trim_end_matches('/'),
+        token,
+        CONFIRM_TOKEN_TTL_HOURS
+      ),
+    })
+    .await
+}
+
+/// Whether enough time has passed since the last confirmation email.
+pub async fn can_resend(pool: &PgPool, user: &User) -> Result<bool, sqlx::Error> {
+  let last = EmailTokenRepository::last_issued_at(pool, &user.id, EmailTokenPurpose::ConfirmEmail).await?;
+  Ok(last.is_none_or(|last| Utc::now() - last >= Duration::seconds(RESEND_COOLDOWN_SECS)))
+}
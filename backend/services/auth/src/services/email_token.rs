@@ -0,0 +1,40 @@
+use chrono::{Duration, Utc};
+use dormmatch_common::{
+  models::email_token::{EmailToken, EmailTokenPurpose},
+  repositories::email_token::EmailTokenRepository,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::services::auth::{hash_token, new_token};
+
+/// Issues a single-use token for `purpose` that expires after `ttl`, and
+/// returns it. Tokens issued earlier for the same purpose stop working.
+pub async fn issue_email_token(
+  pool: &PgPool,
+  user_id: &Uuid,
+  purpose: EmailTokenPurpose,
+  ttl: Duration,
+) -> Result<String, String> {
+  let now = Utc::now();
+  let token = new_token();
+
+  EmailTokenRepository::invalidate(pool, user_id, purpose, now)
+    .await
+    .map_err(|e| format!("Failed to retire old tokens: {}", e))?;
+  EmailTokenRepository::create(
+    pool,
+    &EmailToken {
+      token_hash: hash_token(&token),
+      user_id: *user_id,
+      purpose,
+      created_at: now,
+      expires_at: now + ttl,
+      used_at: None,
+    },
+  )
+  .await
+  .map_err(|e| format!("Failed to store token: {}", e))?;
+
+  Ok(token)
+}
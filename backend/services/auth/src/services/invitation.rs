@@ -0,0 +1,72 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use dormmatch_common::{
+  config::env::Config,
+  mail::{Email, Mailer},
+  models::{invitation::Invitation, user::UserRole},
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What a valid invitation link vouches for.
+pub struct SignedInvitation {
+  pub id: Uuid,
+  pub expires_at: i64,
+}
+
+fn mac(secret: &str, payload: &str) -> HmacSha256 {
+  let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+  mac.update(payload.as_bytes());
+  mac
+}
+
+/// Token for the invitation link: its ID and expiry, signed with `secret`.
+pub fn sign_invitation(secret: &str, invitation: &Invitation) -> String {
+  let payload = format!("{}.{}", invitation.id.simple(), invitation.expires_at.timestamp());
+  let signature = URL_SAFE_NO_PAD.encode(mac(secret, &payload).finalize().into_bytes());
+  format!("{}.{}", payload, signature)
+}
+
+/// Checks the signature of an invitation token. Whether the invitation is
+/// still outstanding is up to the database.
+pub fn verify_invitation(secret: &str, token: &str, now: DateTime<Utc>) -> Option<SignedInvitation> {
+  let (payload, signature) = token.rsplit_once('.')?;
+  let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+  mac(secret, payload).verify_slice(&signature).ok()?;
+
+  let (id, expires_at) = payload.split_once('.')?;
+  let signed = SignedInvitation {
+    id: Uuid::parse_str(id).ok()?,
+    expires_at: expires_at.parse().ok()?,
+  };
+  (signed.expires_at > now.timestamp()).then_some(signed)
+}
+
+/// Emails the invitation link to the invitee.
+pub async fn send_invitation(
+  mailer: &dyn Mailer,
+  config: &Config,
+  invitation: &Invitation,
+  token: &str,
+) -> Result<(), String> {
+  let role = match invitation.role {
+    UserRole::Student => "a student",
+    UserRole::Admin => "a staff member",
+  };
+  mailer
+    .send(&Email {
+      to: invitation.email.clone(),
+      subject: "You are invited to DormMatch".to_string(),
+      body: format!(
+        "Hello,\n\nYou have been invited to join DormMatch as {}. To create your account, open this link:\n\n{}/invitation?token={}\n\nThe link works once and expires on {}.\n",
+        role,
+        config.app_url.trim_end_matches('/'),
+        token,
+        invitation.expires_at.format("%Y-%m-%d %H:%M UTC")
+      ),
+    })
+    .await
+}
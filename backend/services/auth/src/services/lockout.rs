@@ -0,0 +1,165 @@
+use std::net::IpAddr;
+
+use redis::{AsyncCommands, Client};
+
+/// How failed logins against one subject are throttled.
+pub struct Policy {
+  /// Failures allowed before any delay kicks in.
+  pub free_attempts: u64,
+  /// Failures after which the subject is locked out for `lockout` seconds.
+  pub lockout_after: u64,
+  /// First delay in seconds; it doubles with every further failure.
+  pub base_delay: u64,
+  /// Cap on the doubling delay.
+  pub max_delay: u64,
+  pub lockout: u64,
+  /// Seconds after the last failure at which the counter is forgotten.
+  pub window: u64,
+}
+
+/// Guessing passwords for one account.
+pub const EMAIL_POLICY: Policy = Policy {
+  free_attempts: 3,
+  lockout_after: 10,
+  base_delay: 1,
+  max_delay: 60,
+  lockout: 15 * 60,
+  window: 3600,
+};
+
+/// Spraying passwords across accounts. Laxer, since a whole dormitory can
+/// sit behind one address.
+pub const IP_POLICY: Policy = Policy {
+  free_attempts: 20,
+  lockout_after: 100,
+  base_delay: 1,
+  max_delay: 60,
+  lockout: 15 * 60,
+  window: 3600,
+};
+
+/// What failed logins are counted against.
+pub enum LoginSubject<'a> {
+  Email(&'a str),
+  Ip(IpAddr),
+}
+
+impl LoginSubject<'_> {
+  fn id(&self) -> String {
+    match self {
+      LoginSubject::Email(email) => format!("email:{}", email.trim().to_lowercase()),
+      LoginSubject::Ip(ip) => format!("ip:{}", ip),
+    }
+  }
+
+  fn policy(&self) -> &'static Policy {
+    match self {
+      LoginSubject::Email(_) => &EMAIL_POLICY,
+      LoginSubject::Ip(_) => &IP_POLICY,
+    }
+  }
+}
+
+fn failures_key(subject: &LoginSubject) -> String {
+  format!("login_failures:{}", subject.id())
+}
+
+fn lock_key(subject: &LoginSubject) -> String {
+  format!("login_lock:{}", subject.id())
+}
+
+/// Seconds the subject has to wait after its `failures`-th failure in a row,
+/// if any.
+pub fn delay(policy: &Policy, failures: u64) -> Option<u64> {
+  if failures >= policy.lockout_after {
+    return Some(policy.lockout);
+  }
+  let over = failures.checked_sub(policy.free_attempts + 1)?;
+  let delay = policy.base_delay.saturating_mul(1u64 << over.min(32));
+  Some(delay.min(policy.max_delay))
+}
+
+/// Seconds until the longest lock among `subjects` runs out, if any is held.
+pub async fn retry_after(
+  redis: &Client,
+  subjects: &[LoginSubject<'_>],
+) -> Result<Option<u64>, redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  let mut pipe = redis::pipe();
+  for subject in subjects {
+    pipe.ttl(lock_key(subject));
+  }
+  // TTL is negative for keys that do not exist.
+  let ttls: Vec<i64> = pipe.query_async(&mut conn).await?;
+  Ok(ttls.into_iter().filter(|&ttl| ttl > 0).max().map(|ttl| ttl as u64))
+}
+
+/// Counts a failed login against every subject and locks those that went
+/// over their policy. Returns the longest resulting delay, if any.
+pub async fn record_failure(
+  redis: &Client,
+  subjects: &[LoginSubject<'_>],
+) -> Result<Option<u64>, redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  let mut longest = None;
+  for subject in subjects {
+    let policy = subject.policy();
+    let (failures,): (u64,) = redis::pipe()
+      .atomic()
+      .incr(failures_key(subject), 1)
+      .expire(failures_key(subject), policy.window as i64)
+      .ignore()
+      .query_async(&mut conn)
+      .await?;
+    if let Some(delay) = delay(policy, failures) {
+      conn.set_ex::<_, _, ()>(lock_key(subject), failures, delay).await?;
+      longest = longest.max(Some(delay));
+    }
+  }
+  Ok(longest)
+}
+
+/// Forgets the failures of `subject` and lifts its lock.
+pub async fn clear(redis: &Client, subject: &LoginSubject<'_>) -> Result<(), redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  redis::pipe()
+    .del(failures_key(subject))
+    .del(lock_key(subject))
+    .query_async(&mut conn)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn delay_follows_policy() {
+    let table = [
+      (&EMAIL_POLICY, 0, None),
+      (&EMAIL_POLICY, 3, None),
+      (&EMAIL_POLICY, 4, Some(1)),
+      (&EMAIL_POLICY, 5, Some(2)),
+      (&EMAIL_POLICY, 9, Some(32)),
+      (&EMAIL_POLICY, 10, Some(15 * 60)),
+      (&EMAIL_POLICY, 50, Some(15 * 60)),
+      (&IP_POLICY, 20, None),
+      (&IP_POLICY, 26, Some(32)),
+      (&IP_POLICY, 27, Some(60)),
+      (&IP_POLICY, 99, Some(60)),
+      (&IP_POLICY, 100, Some(15 * 60)),
+    ];
+    for (policy, failures, expected) in table {
+      assert_eq!(delay(policy, failures), expected, "{} failures", failures);
+    }
+  }
+
+  #[test]
+  fn delay_does_not_overflow() {
+    let policy = Policy {
+      lockout_after: u64::MAX,
+      ..EMAIL_POLICY
+    };
+    assert_eq!(delay(&policy, 1000), Some(policy.max_delay));
+  }
+}
@@ -1 +1,9 @@
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod confirmation;
+pub mod email_token;
+pub mod invitation;
+pub mod lockout;
+pub mod password_reset;
+pub mod roster;
+pub mod totp;
+pub mod two_factor;
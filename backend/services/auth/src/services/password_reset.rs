@@ -0,0 +1,42 @@
+use chrono::Duration;
+use dormmatch_common::{
+  config::env::Config,
+  mail::{Email, Mailer},
+  models::{email_token::EmailTokenPurpose, user::User},
+};
+use sqlx::PgPool;
+
+use crate::services::email_token::issue_email_token;
+
+/// How long a password reset link stays valid, in minutes.
+pub const RESET_TOKEN_TTL_MINUTES: i64 = 30;
+
+/// Issues a reset token for `user` and emails the link. Links sent earlier
+/// stop working.
+pub async fn send_password_reset(
+  pool: &PgPool,
+  mailer: &dyn Mailer,
+  config: &Config,
+  user: &User,
+) -> Result<(), String> {
+  let token = issue_email_token(
+    pool,
+    &user.id,
+    EmailTokenPurpose::ResetPassword,
+    Duration::minutes(RESET_TOKEN_TTL_MINUTES),
+  )
+  .await?;
+
+  mailer
+    .send(&Email {
+      to: user.email.clone(),
+      subject: "Reset your DormMatch password".to_string(),
+      body: format!(
+        "Hello,\n\nSomeone asked to reset the password of your DormMatch account. To choose a new one, open this link:\n\n{}/reset-password?token={}\n\nThe link expires in {} minutes and works once. If you did not ask for a reset, ignore this email; your password stays the same.\n",
+        config.app_url.trim_end_matches('/'),
+        token,
+        RESET_TOKEN_TTL_MINUTES
+      ),
+    })
+    .await
+}
@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+
+use dormmatch_common::models::{
+  roster::{RosterChange, RosterChangeKind, RosterEntry},
+  user::UserStatus,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// A roster line that could not be imported.
+#[derive(Serialize, ToSchema, Clone)]
+pub struct RosterRowError {
+  /// 1-based line in the uploaded file.
+  pub line: u64,
+  /// Student ID on the line, when there is one. Such students are not
+  /// treated as dropped from the roster.
+  pub student_id: Option<String>,
+  pub message: String,
+}
+
+/// A valid roster line.
+pub struct RosterRow {
+  pub student_id: String,
+  pub email: String,
+  pub faculty: String,
+  pub course: i32,
+}
+
+/// Header names accepted for each column, compared case-insensitively.
+const COLUMNS: [(&str, &[&str]); 4] = [
+  ("student_id", &["student_id", "id"]),
+  ("email", &["email"]),
+  ("faculty", &["faculty"]),
+  ("course", &["course"]),
+];
+
+/// Parses the university roster: a CSV with a header row naming the
+/// `student_id` (or `id`), `email`, `faculty` and `course` columns in any
+/// order. Malformed and duplicate rows are reported and skipped; the error
+/// is only for a file that cannot be read at all.
+pub fn parse_roster(data: &[u8]) -> Result<(Vec<RosterRow>, Vec<RosterRowError>), String> {
+  let mut reader = csv::ReaderBuilder::new()
+    .trim(csv::Trim::All)
+    .flexible(true)
+    .from_reader(data);
+
+  let headers = reader
+    .headers()
+    .map_err(|e| format!("Cannot read header row: {}", e))?
+    .clone();
+  let mut positions = [0usize; 4];
+  for (slot, (name, aliases)) in positions.iter_mut().zip(COLUMNS) {
+    *slot = headers
+      .iter()
+      .position(|header| aliases.iter().any(|alias| header.eq_ignore_ascii_case(alias)))
+      .ok_or_else(|| format!("Missing column {}", name))?;
+  }
+
+  let mut rows = Vec::new();
+  let mut errors = Vec::new();
+  let mut seen_ids: HashMap<String, u64> = HashMap::new();
+  let mut seen_emails: HashMap<String, u64> = HashMap::new();
+
+  for record in reader.records() {
+    let record = match record {
+      Ok(record) => record,
+      Err(e) => {
+        let line = e.position().map_or(0, |p| p.line());
+        errors.push(RosterRowError {
+          line,
+          student_id: None,
+          message: format!("Unreadable row: {}", e),
+        });
+        continue;
+      }
+    };
+    let line = record.position().map_or(0, |p| p.line());
+    let [student_id, email, faculty, course] = positions.map(|i| record.get(i).unwrap_or(""));
+
+    let row = parse_row(student_id, email, faculty, course).and_then(|row| {
+      if let Some(first) = seen_ids.get(&row.student_id) {
+        return Err(format!("Duplicate student ID {} (first on line {})", row.student_id, first));
+      }
+      if let Some(first) = seen_emails.get(&row.email.to_lowercase()) {
+        return Err(format!("Duplicate email {} (first on line {})", row.email, first));
+      }
+      Ok(row)
+    });
+    match row {
+      Ok(row) => {
+        seen_ids.insert(row.student_id.clone(), line);
+        seen_emails.insert(row.email.to_lowercase(), line);
+        rows.push(row);
+      }
+      Err(message) => errors.push(RosterRowError {
+        line,
+        student_id: (!student_id.is_empty()).then(|| student_id.to_string()),
+        message,
+      }),
+    }
+  }
+
+  Ok((rows, errors))
+}
+
+fn parse_row(student_id: &str, email: &str, faculty: &str, course: &str) -> Result<RosterRow, String> {
+  if student_id.is_empty() {
+    return Err("Missing student ID".to_string());
+  }
+  match email.split_once('@') {
+    Some((local, domain)) if !local.is_empty() && domain.contains('.') => {}
+    _ => return Err(format!("Invalid email {:?}", email)),
+  }
+  if faculty.is_empty() || faculty.chars().count() > 100 {
+    return Err(format!("Invalid faculty {:?}", faculty));
+  }
+  let course = course
+    .parse::<i32>()
+    .ok()
+    .filter(|&course| course >= 1)
+    .ok_or_else(|| format!("Invalid course {:?}", course))?;
+
+  Ok(RosterRow {
+    student_id: student_id.to_string(),
+    email: email.to_string(),
+    faculty: faculty.to_string(),
+    course,
+  })
+}
+
+/// What registration should do with a student given their roster entry.
+///
+/// A listed email with the same faculty verifies the account and a different
+/// faculty rejects it. A student missing from the roster, or listed with
+/// another course, is left pending for a human to decide.
+pub fn roster_verdict(entry: Option<&RosterEntry>, faculty: &str, course: i32) -> Option<UserStatus> {
+  let entry = entry?;
+  if entry.faculty.trim().to_lowercase() != faculty.trim().to_lowercase() {
+    return Some(UserStatus::Rejected);
+  }
+  (entry.course == course).then_some(UserStatus::Verified)
+}
+
+/// Diff between the current roster and a newly imported one.
+///
+/// Students in `rows` but not in `previous` are added, students whose
+/// faculty or course differ are changed, and students missing from `rows`
+/// are removed, except those listed in `kept` (rows that failed to parse).
+pub fn diff_roster(
+  import_id: uuid::Uuid,
+  previous: &[RosterEntry],
+  rows: &[RosterRow],
+  kept: &HashSet<&str>,
+) -> Vec<RosterChange> {
+  let before: HashMap<&str, &RosterEntry> = previous
+    .iter()
+    .map(|entry| (entry.student_id.as_str(), entry))
+    .collect();
+  let mut changes = Vec::new();
+  for row in rows {
+    let entry = before.get(row.student_id.as_str()).copied();
+    let kind = match entry {
+      None => RosterChangeKind::Added,
+      Some(entry) if entry.faculty != row.faculty || entry.course != row.course => RosterChangeKind::Changed,
+      Some(_) => continue,
+    };
+    changes.push(RosterChange {
+      import_id,
+      student_id: row.student_id.clone(),
+      kind,
+      email: row.email.clone(),
+      old_faculty: entry.map(|entry| entry.faculty.clone()),
+      new_faculty: Some(row.faculty.clone()),
+      old_course: entry.map(|entry| entry.course),
+      new_course: Some(row.course),
+      user_id: None,
+    });
+  }
+
+  let listed: HashSet<&str> = rows.iter().map(|row| row.student_id.as_str()).collect();
+  for entry in previous {
+    let id = entry.student_id.as_str();
+    if listed.contains(id) || kept.contains(id) {
+      continue;
+    }
+    changes.push(RosterChange {
+      import_id,
+      student_id: entry.student_id.clone(),
+      kind: RosterChangeKind::Removed,
+      email: entry.email.clone(),
+      old_faculty: Some(entry.faculty.clone()),
+      new_faculty: None,
+      old_course: Some(entry.course),
+      new_course: None,
+      user_id: None,
+    });
+  }
+  changes
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Utc;
+
+  fn entry(student_id: &str, faculty: &str, course: i32) -> RosterEntry {
+    RosterEntry {
+      student_id: student_id.to_string(),
+      email: format!("{}@uni.edu", student_id),
+      faculty: faculty.to_string(),
+      course,
+      import_id: uuid::Uuid::nil(),
+      updated_at: Utc::now(),
+    }
+  }
+
+  #[test]
+  fn accepts_header_aliases_in_any_order() {
+    for header in ["student_id,email,faculty,course", "ID,Email,Faculty,Course"] {
+      let data = format!("{}\nS1,a@uni.edu,Physics,2\n", header);
+      let (rows, errors) = parse_roster(data.as_bytes()).unwrap();
+      assert!(errors.is_empty(), "{}", header);
+      assert_eq!(rows.len(), 1);
+      assert_eq!(rows[0].student_id, "S1");
+    }
+
+    let (rows, _) = parse_roster(b"course, faculty ,email,id\n3,Math,b@uni.edu,S2\n").unwrap();
+    assert_eq!(
+      (
+        rows[0].student_id.as_str(),
+        rows[0].faculty.as_str(),
+        rows[0].course
+      ),
+      ("S2", "Math", 3)
+    );
+  }
+
+  #[test]
+  fn refuses_file_without_required_column() {
+    assert!(parse_roster(b"student_id,email,faculty\nS1,a@uni.edu,Physics\n").is_err());
+  }
+
+  #[test]
+  fn skips_malformed_rows() {
+    let data = "\
+student_id,email,faculty,course
+S1,a@uni.edu,Physics,2
+,b@uni.edu,Physics,2
+S3,not-an-email,Physics,2
+S4,d@uni.edu,,2
+S5,e@uni.edu,Physics,0
+S6,f@uni.edu,Physics,second
+S7,g@uni.edu
+S1,h@uni.edu,Physics,2
+S9,A@UNI.EDU,Physics,2
+";
+    let (rows, errors) = parse_roster(data.as_bytes()).unwrap();
+    assert_eq!(
+      rows
+        .iter()
+        .map(|row| row.student_id.as_str())
+        .collect::<Vec<_>>(),
+      ["S1"]
+    );
+    assert_eq!(
+      errors.iter().map(|error| error.line).collect::<Vec<_>>(),
+      [3, 4, 5, 6, 7, 8, 9, 10]
+    );
+    assert_eq!(errors[0].student_id, None);
+    assert_eq!(errors[1].student_id.as_deref(), Some("S3"));
+  }
+
+  #[test]
+  fn verdict_ignores_faculty_case_and_whitespace() {
+    let listed = entry("S1", "Physics", 2);
+    assert!(matches!(
+      roster_verdict(Some(&listed), "  physics ", 2),
+      Some(UserStatus::Verified)
+    ));
+    assert!(matches!(
+      roster_verdict(Some(&listed), "Chemistry", 2),
+      Some(UserStatus::Rejected)
+    ));
+  }
+
+  #[test]
+  fn verdict_leaves_course_mismatch_and_unlisted_pending() {
+    let listed = entry("S1", "Physics", 2);
+    assert!(roster_verdict(Some(&listed), "Physics", 3).is_none());
+    assert!(roster_verdict(None, "Physics", 2).is_none());
+  }
+
+  fn row(student_id: &str, faculty: &str, course: i32) -> RosterRow {
+    RosterRow {
+      student_id: student_id.to_string(),
+      email: format!("{}@uni.edu", student_id),
+      faculty: faculty.to_string(),
+      course,
+    }
+  }
+
+  #[test]
+  fn diff_reports_added_changed_and_removed() {
+    let previous = [
+      entry("S1", "Physics", 2),
+      entry("S2", "Physics", 2),
+      entry("S3", "Math", 1),
+      entry("S4", "Math", 1),
+    ];
+    let rows = [
+      row("S1", "Physics", 2),
+      row("S2", "Physics", 3),
+      row("S3", "Chemistry", 1),
+      row("S5", "Math", 1),
+    ];
+    let changes = diff_roster(uuid::Uuid::nil(), &previous, &rows, &HashSet::new());
+    let summary: Vec<_> = changes
+      .iter()
+      .map(|change| (change.student_id.as_str(), change.kind))
+      .collect();
+    assert_eq!(
+      summary,
+      [
+        ("S2", RosterChangeKind::Changed),
+        ("S3", RosterChangeKind::Changed),
+        ("S5", RosterChangeKind::Added),
+        ("S4", RosterChangeKind::Removed),
+      ]
+    );
+
+    let changed = &changes[0];
+    assert_eq!((changed.old_course, changed.new_course), (Some(2), Some(3)));
+    let added = &changes[2];
+    assert_eq!(
+      (added.old_faculty.as_deref(), added.new_faculty.as_deref()),
+      (None, Some("Math"))
+    );
+    let removed = &changes[3];
+    assert_eq!((removed.old_course, removed.new_course), (Some(1), None));
+  }
+
+  #[test]
+  fn diff_keeps_students_on_unparsed_rows() {
+    let previous = [entry("S1", "Physics", 2), entry("S2", "Physics", 2)];
+    let kept = HashSet::from(["S2"]);
+    let changes = diff_roster(uuid::Uuid::nil(), &previous, &[], &kept);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].student_id, "S1");
+    assert_eq!(changes[0].kind, RosterChangeKind::Removed);
+  }
+}
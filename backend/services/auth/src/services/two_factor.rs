@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use dormmatch_common::{models::two_factor::TotpCredential, repositories::two_factor::TwoFactorRepository};
+use rand::{rngs::OsRng, RngCore};
+use redis::{AsyncCommands, Client};
+use sqlx::PgPool;
+
+use crate::services::{
+  auth::{hash_token, new_token},
+  totp::verify_code,
+};
+
+/// How long the second login step may take, in seconds.
+pub const CHALLENGE_TTL: u64 = 300;
+
+/// Wrong codes allowed per challenge before the password is asked again.
+pub const MAX_CHALLENGE_ATTEMPTS: u64 = 5;
+
+/// Recovery codes handed out per enrollment.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+fn challenge_key(token: &str) -> String {
+  format!("two_factor:{}", hash_token(token))
+}
+
+/// Remembers that `user_id` got the password right and returns the token
+/// for the second step.
+pub async fn issue_challenge(redis: &Client, user_id: &str) -> Result<String, redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  let token = new_token();
+  redis::pipe()
+    .atomic()
+    .hset(challenge_key(&token), "user_id", user_id)
+    .expire(challenge_key(&token), CHALLENGE_TTL as i64)
+    .query_async::<_, ()>(&mut conn)
+    .await?;
+  Ok(token)
+}
+
+/// The user a challenge was issued to, if it is still open.
+pub async fn challenge_user(redis: &Client, token: &str) -> Result<Option<String>, redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  conn.hget(challenge_key(token), "user_id").await
+}
+
+/// Counts a wrong code; the challenge is dropped after too many.
+pub async fn fail_challenge(redis: &Client, token: &str) -> Result<(), redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  let attempts: u64 = conn.hincr(challenge_key(token), "attempts", 1).await?;
+  if attempts >= MAX_CHALLENGE_ATTEMPTS {
+    conn.del::<_, ()>(challenge_key(token)).await?;
+  }
+  Ok(())
+}
+
+/// Closes the challenge. Returns false if it was already closed, so only
+/// one request gets to finish the login.
+pub async fn finish_challenge(redis: &Client, token: &str) -> Result<bool, redis::RedisError> {
+  let mut conn = redis.get_multiplexed_async_connection().await?;
+  let deleted: u64 = conn.del(challenge_key(token)).await?;
+  Ok(deleted == 1)
+}
+
+/// Fresh recovery codes, formatted for reading off a printout.
+pub fn new_recovery_codes() -> Vec<String> {
+  (0..RECOVERY_CODE_COUNT)
+    .map(|_| {
+      let mut bytes = [0u8; 5];
+      OsRng.fill_bytes(&mut bytes);
+      let code = hex::encode(bytes);
+      format!("{}-{}", &code[..5], &code[5..])
+    })
+    .collect()
+}
+
+/// Hash a recovery code is stored under; dashes and case do not matter.
+pub fn hash_recovery_code(code: &str) -> String {
+  let normalized: String = code
+    .chars()
+    .filter(|c| c.is_ascii_alphanumeric())
+    .map(|c| c.to_ascii_lowercase())
+    .collect();
+  hash_token(&normalized)
+}
+
+/// Accepts a TOTP code that was not used before, or else an unused
+/// recovery code, which is then spent.
+pub async fn check_second_factor(
+  pool: &PgPool,
+  credential: &TotpCredential,
+  code: &str,
+  now: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+  match verify_code(&credential.secret, code, now.timestamp() as u64) {
+    Some(step) => TwoFactorRepository::use_step(pool, &credential.user_id, step as i64).await,
+    None => {
+      TwoFactorRepository::use_recovery_code(pool, &credential.user_id, &hash_recovery_code(code), now).await
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn recovery_code_hash_ignores_dashes_case_and_spaces() {
+    let hash = hash_recovery_code("a1b2c-3d4e5");
+    assert_eq!(hash_recovery_code("A1B2C-3D4E5"), hash);
+    assert_eq!(hash_recovery_code("a1b2c3d4e5"), hash);
+    assert_eq!(hash_recovery_code(" a1b2c 3d4e5 "), hash);
+    assert_ne!(hash_recovery_code("a1b2c-3d4e6"), hash);
+  }
+
+  #[test]
+  fn recovery_codes_are_distinct_and_readable() {
+    let codes = new_recovery_codes();
+    assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+    for code in &codes {
+      assert_eq!(code.len(), 11);
+      assert_eq!(code.as_bytes()[5], b'-');
+    }
+    let unique: std::collections::HashSet<&String> = codes.iter().collect();
+    assert_eq!(unique.len(), codes.len());
+  }
+}
@@ -0,0 +1,581 @@
+use std::collections::{HashMap, HashSet};
+
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  middleware::role::StaffAccess,
+  models::{
+    allocation_run::AllocationRun,
+    application::{Application, ApplicationStatus},
+    lottery::{Lottery, LotteryEntry},
+    preference::SocialBenefit,
+    role_assignment::{Permission, Scope},
+  },
+  repositories::{
+    allocation_run::AllocationRunRepository,
+    lottery::LotteryRepository,
+    matching_settings::MatchingSettingsRepository,
+    preference::{RoomPreferenceRepository, SocialBenefitRepository},
+    profile::{PostgresStudentProfileRepository, StudentProfileRepository},
+    room::RoomRepository,
+  },
+};
+use sqlx::{types::chrono::Utc, PgPool};
+use uuid::Uuid;
+
+use crate::models::{
+  AllocationPlan, AllocationRequest, DeferredAcceptanceRequest, LotteryDraw, LotteryResult,
+  PlanMetrics, SocialBenefitRequest,
+};
+use crate::services::{
+  allocation::{self, AllocationInput},
+  lottery,
+};
+
+/// Loads the verified, unhoused students together with every available room
+/// at least one of them may live in.
+async fn load_input(pool: &PgPool) -> Result<AllocationInput, actix_web::Error> {
+  let settings = MatchingSettingsRepository::find_active(pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Settings lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorInternalServerError("No active matching settings"))?;
+
+  let students = PostgresStudentProfileRepository::find_unhoused_verified(pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Student lookup failed: {}", e)))?;
+
+  let groups: HashSet<(&str, i32, &str)> = students
+    .iter()
+    .map(|s| (s.faculty.as_str(), s.course, s.gender.as_str()))
+    .collect();
+  let mut rooms = HashMap::new();
+  for (faculty, course, sex) in groups {
+    let available = RoomRepository::find_available(pool, Some(faculty), Some(course), sex)
+      .await
+      .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room search failed: {}", e)))?;
+    rooms.extend(available.into_iter().map(|room| (room.id, room)));
+  }
+  let mut rooms: Vec<_> = rooms.into_values().collect();
+  rooms.sort_by(|a, b| a.number.cmp(&b.number));
+
+  let room_ids: Vec<Uuid> = rooms.iter().map(|room| room.id).collect();
+  let occupants = PostgresStudentProfileRepository::find_occupants_by_room_ids(pool, &room_ids)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Occupant lookup failed: {}", e)))?;
+
+  Ok(AllocationInput {
+    students,
+    rooms,
+    occupants,
+    settings,
+  })
+}
+
+/// Every student's ranked rooms, best first.
+async fn load_preferences(pool: &PgPool) -> Result<HashMap<Uuid, Vec<Uuid>>, actix_web::Error> {
+  let mut preferences: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+  for preference in RoomPreferenceRepository::find_all(pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Preference lookup failed: {}", e)))?
+  {
+    preferences
+      .entry(preference.user_id)
+      .or_default()
+      .push(preference.room_id);
+  }
+  Ok(preferences)
+}
+
+/// Turns a plan into a new run and its applications: a pending one per
+/// placement, and a rejected one for every unplaced student the mode ties to
+/// a room.
+fn prepare_commit(plan: &AllocationPlan, comment: &str) -> (AllocationRun, Vec<Application>) {
+  let run = AllocationRun {
+    id: Uuid::new_v4(),
+    mode: plan.mode.clone(),
+    matching_settings_version: Some(plan.matching_settings_version),
+    summary: serde_json::json!({
+      "metrics": &plan.metrics,
+      "pairing": &plan.pairing,
+      "priorities": &plan.priorities,
+    }),
+    created_at: Utc::now(),
+  };
+
+  let application = |user_id: Uuid, room_id: Uuid, status: ApplicationStatus, comment: String| Application {
+    id: Uuid::new_v4(),
+    user_id,
+    room_id,
+    status,
+    comment: Some(comment),
+    created_at: run.created_at,
+    matching_settings_version: Some(plan.matching_settings_version),
+    allocation_run_id: Some(run.id),
+  };
+
+  let placed = plan.placements.iter().map(|placement| {
+    let comment = match &placement.note {
+      Some(note) => format!("{}: {}", comment, note),
+      None => format!("{} (match {:.0}%)", comment, placement.score),
+    };
+    application(placement.user_id, placement.room_id, ApplicationStatus::Pending, comment)
+  });
+  let refused = plan.unplaced.iter().filter_map(|unplaced| {
+    let comment = format!("{}: {}", comment, unplaced.reason);
+    unplaced
+      .room_id
+      .map(|room_id| application(unplaced.user_id, room_id, ApplicationStatus::Rejected, comment))
+  });
+  let applications: Vec<Application> = placed.chain(refused).collect();
+
+  (run, applications)
+}
+
+async fn commit_plan(
+  pool: &PgPool,
+  plan: &mut AllocationPlan,
+  comment: &str,
+) -> Result<(), actix_web::Error> {
+  let (run, applications) = prepare_commit(plan, comment);
+  let (run, _) = AllocationRunRepository::create_with_applications(pool, &run, &applications)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to commit plan: {}", e)))?;
+
+  plan.run_id = Some(run.id);
+  Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/allocations/batch",
+    request_body = AllocationRequest,
+    responses(
+        (status = 200, description = "Proposed or committed allocation plan", body = AllocationPlan),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn run_batch_allocation(
+  staff: StaffAccess,
+  body: web::Json<AllocationRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  let dry_run = body.dry_run.unwrap_or(true);
+  let input = load_input(&pool).await?;
+
+  let (placements, unplaced) = allocation::plan_batch(&input);
+  let mut plan = AllocationPlan {
+    mode: "batch".to_string(),
+    matching_settings_version: input.settings.version,
+    dry_run,
+    run_id: None,
+    metrics: PlanMetrics::new(
+      input.students.len(),
+      input.rooms.len(),
+      &placements,
+      input.settings.min_score,
+    ),
+    placements,
+    unplaced,
+    pairing: None,
+    priorities: None,
+    lottery: None,
+  };
+
+  if !dry_run {
+    commit_plan(&pool, &mut plan, "Batch allocation").await?;
+  }
+
+  Ok(HttpResponse::Ok().json(plan))
+}
+
+#[utoipa::path(
+    post,
+    path = "/allocations/stable-roommates",
+    request_body = AllocationRequest,
+    responses(
+        (status = 200, description = "Proposed or committed pairing of students into two-person rooms", body = AllocationPlan),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn run_stable_roommates_allocation(
+  staff: StaffAccess,
+  body: web::Json<AllocationRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  let dry_run = body.dry_run.unwrap_or(true);
+  let input = load_input(&pool).await?;
+
+  let (placements, unplaced, pairing) = allocation::plan_pairs(&input);
+  let mut plan = AllocationPlan {
+    mode: "stable_roommates".to_string(),
+    matching_settings_version: input.settings.version,
+    dry_run,
+    run_id: None,
+    metrics: PlanMetrics::new(
+      input.students.len(),
+      input.rooms.len(),
+      &placements,
+      input.settings.min_score,
+    ),
+    placements,
+    unplaced,
+    pairing: Some(pairing),
+    priorities: None,
+    lottery: None,
+  };
+
+  if !dry_run {
+    commit_plan(&pool, &mut plan, "Stable roommates pairing").await?;
+  }
+
+  Ok(HttpResponse::Ok().json(plan))
+}
+
+#[utoipa::path(
+    post,
+    path = "/allocations/deferred-acceptance",
+    request_body = DeferredAcceptanceRequest,
+    responses(
+        (status = 200, description = "Proposed or committed admission of students to their ranked rooms", body = AllocationPlan),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn run_deferred_acceptance(
+  staff: StaffAccess,
+  body: web::Json<DeferredAcceptanceRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  let body = body.into_inner();
+  let dry_run = body.dry_run.unwrap_or(true);
+  let input = load_input(&pool).await?;
+
+  let preferences = load_preferences(&pool).await?;
+  let benefits: HashSet<Uuid> = SocialBenefitRepository::find_all(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Benefit lookup failed: {}", e)))?
+    .into_iter()
+    .map(|benefit| benefit.user_id)
+    .collect();
+
+  let (placements, unplaced) =
+    allocation::plan_deferred_acceptance(&input, &preferences, &benefits, &body.priorities);
+  let participants = input
+    .students
+    .iter()
+    .filter(|s| preferences.contains_key(&s.user_id))
+    .count();
+  let mut plan = AllocationPlan {
+    mode: "deferred_acceptance".to_string(),
+    matching_settings_version: input.settings.version,
+    dry_run,
+    run_id: None,
+    metrics: PlanMetrics::new(participants, input.rooms.len(), &placements, input.settings.min_score),
+    placements,
+    unplaced,
+    pairing: None,
+    priorities: Some(body.priorities),
+    lottery: None,
+  };
+
+  if !dry_run {
+    commit_plan(&pool, &mut plan, "Deferred acceptance").await?;
+  }
+
+  Ok(HttpResponse::Ok().json(plan))
+}
+
+#[utoipa::path(
+    put,
+    path = "/allocations/benefits/{user_id}",
+    params(
+        ("user_id", Path, description = "User ID")
+    ),
+    request_body = SocialBenefitRequest,
+    responses(
+        (status = 200, description = "Social-benefit category set", body = SocialBenefit),
+        (status = 400, description = "Invalid category", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn set_social_benefit(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  body: web::Json<SocialBenefitRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  let category = body.category.trim();
+  if category.is_empty() {
+    return Err(actix_web::error::ErrorBadRequest("Category must not be empty"));
+  }
+
+  let benefit = SocialBenefitRepository::upsert(&pool, &path.into_inner(), category)
+    .await
+    .map_err(|e| actix_web::error::ErrorBadRequest(format!("Failed to set benefit: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(benefit))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/allocations/benefits/{user_id}",
+    params(
+        ("user_id", Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "Social-benefit category removed"),
+        (status = 404, description = "User has no category", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn remove_social_benefit(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  let removed = SocialBenefitRepository::delete(&pool, &path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to remove benefit: {}", e)))?;
+
+  if removed {
+    Ok(HttpResponse::NoContent().finish())
+  } else {
+    Err(actix_web::error::ErrorNotFound("User has no social-benefit category"))
+  }
+}
+
+#[utoipa::path(
+    post,
+    path = "/allocations/lotteries",
+    responses(
+        (status = 201, description = "Lottery announced with the hash of its seed; the current room rankings become its applicants", body = Lottery),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String),
+        (status = 409, description = "Another lottery has not been drawn yet", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn announce_lottery(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  // Only the hash goes out now. The seed is revealed by the draw, so nobody
+  // can work out the result while the lottery is open, and anyone can check
+  // afterwards that the seed was fixed in advance.
+  let seed = lottery::generate_seed();
+  let lottery = LotteryRepository::create(&pool, &Uuid::new_v4(), &seed, &lottery::seed_hash(&seed))
+    .await
+    .map_err(|e| match e.as_database_error() {
+      Some(db) if db.is_unique_violation() => {
+        actix_web::error::ErrorConflict("Another lottery is still open; draw it first")
+      }
+      _ => actix_web::error::ErrorInternalServerError(format!("Failed to announce lottery: {}", e)),
+    })?;
+
+  Ok(HttpResponse::Created().json(lottery))
+}
+
+#[utoipa::path(
+    get,
+    path = "/allocations/lotteries",
+    responses(
+        (status = 200, description = "Lotteries, most recent first", body = [Lottery])
+    )
+)]
+pub async fn list_lotteries(pool: web::Data<PgPool>) -> Result<HttpResponse, actix_web::Error> {
+  let lotteries = LotteryRepository::find_all(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get lotteries: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(lotteries))
+}
+
+#[utoipa::path(
+    get,
+    path = "/allocations/lotteries/{id}",
+    params(
+        ("id", Path, description = "Lottery ID")
+    ),
+    responses(
+        (status = 200, description = "Lottery with its seed hash, and once drawn its seed and recorded draws", body = LotteryResult),
+        (status = 404, description = "Lottery not found", body = String)
+    )
+)]
+pub async fn get_lottery(
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let lottery = find_lottery(&pool, &path.into_inner()).await?;
+  let entries = LotteryRepository::find_entries(&pool, &lottery.id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get draws: {}", e)))?;
+
+  let mut draws: Vec<LotteryDraw> = Vec::new();
+  for entry in entries {
+    if draws.last().is_none_or(|draw| draw.room_id != entry.room_id) {
+      let room = RoomRepository::find_by_id(&pool, &entry.room_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?;
+      draws.push(LotteryDraw {
+        room_id: entry.room_id,
+        room_number: room.map(|room| room.number).unwrap_or_default(),
+        drawn: Vec::new(),
+        admitted: Vec::new(),
+      });
+    }
+    let draw = draws.last_mut().unwrap();
+    draw.drawn.push(entry.user_id);
+    if entry.admitted {
+      draw.admitted.push(entry.user_id);
+    }
+  }
+  draws.sort_by(|a, b| a.room_number.cmp(&b.room_number));
+
+  Ok(HttpResponse::Ok().json(LotteryResult { lottery, draws }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/allocations/lotteries/{id}/draw",
+    params(
+        ("id", Path, description = "Lottery ID")
+    ),
+    request_body = AllocationRequest,
+    responses(
+        (status = 200, description = "Committed lottery allocation with the revealed seed", body = AllocationPlan),
+        (status = 400, description = "Dry run requested; a lottery cannot be previewed", body = String),
+        (status = 404, description = "Lottery not found", body = String),
+        (status = 409, description = "Lottery already drawn", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn draw_lottery(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  body: web::Json<AllocationRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  // A preview would show the outcome before it is final, leaving room to
+  // change rooms or applicants until it suits.
+  if body.dry_run.unwrap_or(true) {
+    return Err(actix_web::error::ErrorBadRequest(
+      "A lottery cannot be previewed; draw it with dry_run set to false",
+    ));
+  }
+  let lottery = find_lottery(&pool, &path.into_inner()).await?;
+  if lottery.drawn_at.is_some() {
+    return Err(actix_web::error::ErrorConflict("Lottery has already been drawn"));
+  }
+  let seed = LotteryRepository::find_seed(&pool, &lottery.id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Lottery lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Lottery not found"))?;
+
+  let input = load_input(&pool).await?;
+  // Applicants are those who had ranked rooms when the lottery was announced.
+  let preferences = LotteryRepository::find_preferences(&pool, &lottery.id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Preference lookup failed: {}", e)))?;
+
+  let (placements, unplaced, draws) = allocation::plan_lottery(&input, &preferences, &seed);
+  let participants = input
+    .students
+    .iter()
+    .filter(|s| preferences.contains_key(&s.user_id))
+    .count();
+  let mut plan = AllocationPlan {
+    mode: "lottery".to_string(),
+    matching_settings_version: input.settings.version,
+    dry_run: false,
+    run_id: None,
+    metrics: PlanMetrics::new(participants, input.rooms.len(), &placements, input.settings.min_score),
+    placements,
+    unplaced,
+    pairing: None,
+    priorities: None,
+    lottery: Some(LotteryResult { lottery, draws }),
+  };
+
+  let (run, applications) = prepare_commit(&plan, "Lottery");
+  let result = plan.lottery.as_mut().unwrap();
+  let entries: Vec<LotteryEntry> = result
+    .draws
+    .iter()
+    .flat_map(|draw| {
+      draw
+        .drawn
+        .iter()
+        .enumerate()
+        .map(|(position, user_id)| LotteryEntry {
+          lottery_id: result.lottery.id,
+          room_id: draw.room_id,
+          position: position as i32,
+          user_id: *user_id,
+          admitted: draw.admitted.contains(user_id),
+        })
+    })
+    .collect();
+
+  result.lottery =
+    LotteryRepository::record_draw(&pool, &result.lottery.id, &entries, &run, &applications)
+      .await
+      .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to record draw: {}", e)))?
+      .ok_or_else(|| actix_web::error::ErrorConflict("Lottery has already been drawn"))?;
+  plan.run_id = Some(run.id);
+
+  Ok(HttpResponse::Ok().json(plan))
+}
+
+async fn find_lottery(pool: &PgPool, id: &Uuid) -> Result<Lottery, actix_web::Error> {
+  LotteryRepository::find_by_id(pool, id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Lottery lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Lottery not found"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/allocations",
+    responses(
+        (status = 200, description = "Committed allocation runs, newest first", body = [AllocationRun]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the run_allocations permission", body = String)
+    ),
+    security(("bearerAuth" = ["run_allocations"]))
+)]
+pub async fn list_allocation_runs(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::RunAllocations, Scope::global())?;
+
+  let runs = AllocationRunRepository::find_all(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to list runs: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(runs))
+}
@@ -1 +1,5 @@
 pub mod rooms;
+pub mod settings;
+pub mod allocations;
+pub mod preferences;
+pub mod residencies;
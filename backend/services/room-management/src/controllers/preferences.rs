@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  models::preference::RoomPreference,
+  repositories::{
+    lottery::LotteryRepository,
+    preference::RoomPreferenceRepository,
+    profile::{PostgresStudentProfileRepository, StudentProfileRepository},
+    room::RoomRepository,
+  },
+  utils::jwt::Claims,
+};
+use sqlx::PgPool;
+
+use crate::models::RoomPreferencesRequest;
+use crate::services::matching::MatchingService;
+
+/// How many rooms a student may rank.
+pub const MAX_ROOM_PREFERENCES: usize = 5;
+
+#[utoipa::path(
+    put,
+    path = "/rooms/preferences",
+    request_body = RoomPreferencesRequest,
+    responses(
+        (status = 200, description = "The caller's ranking saved", body = [RoomPreference]),
+        (status = 400, description = "Invalid ranking", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 409, description = "Rankings are frozen while a lottery is open", body = String)
+    ),
+    security(("bearerAuth" = []))
+)]
+pub async fn set_preferences(
+  claims: web::ReqData<Claims>,
+  body: web::Json<RoomPreferencesRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let user_id = claims
+    .user_id()
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+  let body = body.into_inner();
+  if body.room_ids.len() > MAX_ROOM_PREFERENCES {
+    return Err(actix_web::error::ErrorBadRequest(format!(
+      "At most {} rooms can be ranked",
+      MAX_ROOM_PREFERENCES
+    )));
+  }
+  if body.room_ids.iter().collect::<HashSet<_>>().len() != body.room_ids.len() {
+    return Err(actix_web::error::ErrorBadRequest("A room can be ranked only once"));
+  }
+
+  // The open lottery draws from the rankings as they were when it was
+  // announced; changes now would only look like they count.
+  let open = LotteryRepository::find_open(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Lottery lookup failed: {}", e)))?;
+  if open.is_some() {
+    return Err(actix_web::error::ErrorConflict(
+      "Rankings cannot change while a lottery is open",
+    ));
+  }
+
+  let profile = PostgresStudentProfileRepository::find_by_user_id(pool.get_ref(), &user_id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Profile lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorBadRequest("Profile not found"))?;
+
+  for room_id in &body.room_ids {
+    let room = RoomRepository::find_by_id(&pool, room_id)
+      .await
+      .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+      .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Room {} not found", room_id)))?;
+    if !MatchingService::is_compatible(&room, &profile) {
+      return Err(actix_web::error::ErrorBadRequest(format!(
+        "Room {} is not available to this student",
+        room.number
+      )));
+    }
+  }
+
+  let preferences = RoomPreferenceRepository::replace(&pool, &user_id, &body.room_ids)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save ranking: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(preferences))
+}
+
+#[utoipa::path(
+    get,
+    path = "/rooms/preferences",
+    responses(
+        (status = 200, description = "The caller's ranking, best first", body = [RoomPreference]),
+        (status = 401, description = "Missing or invalid token", body = String)
+    ),
+    security(("bearerAuth" = []))
+)]
+pub async fn get_preferences(
+  claims: web::ReqData<Claims>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let user_id = claims
+    .user_id()
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+
+  let preferences = RoomPreferenceRepository::find_by_user_id(&pool, &user_id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get ranking: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(preferences))
+}
@@ -0,0 +1,167 @@
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  middleware::role::StaffAccess,
+  models::{
+    application::ApplicationStatus,
+    residency::Residency,
+    role_assignment::{Permission, Scope},
+    room::{Room, RoomStatus},
+  },
+  repositories::{
+    application::ApplicationRepository, residency::ResidencyRepository, room::RoomRepository,
+  },
+  utils::jwt::Claims,
+};
+use sqlx::{types::chrono::Utc, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::CurrentResidency;
+
+#[utoipa::path(
+    get,
+    path = "/rooms/{id}/residents",
+    params(
+        ("id", Path, description = "Room ID")
+    ),
+    responses(
+        (status = 200, description = "Current residents of the room", body = [Residency]),
+        (status = 404, description = "Room not found", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_residencies permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_residencies"]))
+)]
+pub async fn get_room_residents(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let room = RoomRepository::find_by_id(&pool, &path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+  staff.require(Permission::ManageResidencies, Scope::building(room.building.as_deref()))?;
+
+  let residents = ResidencyRepository::find_active_by_room_id(&pool, &room.id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get residents: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(residents))
+}
+
+#[utoipa::path(
+    get,
+    path = "/residencies/current",
+    responses(
+        (status = 200, description = "The room the caller currently lives in", body = CurrentResidency),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 404, description = "Caller is not housed", body = String)
+    ),
+    security(("bearerAuth" = []))
+)]
+pub async fn get_current_residency(
+  claims: web::ReqData<Claims>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let user_id = claims
+    .user_id()
+    .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+
+  let residency = ResidencyRepository::find_active_by_user_id(&**pool, &user_id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Residency lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Student is not housed"))?;
+
+  let room: Room = RoomRepository::find_by_id(&pool, &residency.room_id)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+
+  Ok(HttpResponse::Ok().json(CurrentResidency { residency, room }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/residencies/{id}/move-out",
+    params(
+        ("id", Path, description = "Residency ID")
+    ),
+    responses(
+        (status = 200, description = "Residency ended, the bed freed and the linked application withdrawn or checked out", body = Residency),
+        (status = 404, description = "Residency not found", body = String),
+        (status = 409, description = "Residency has already ended", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_residencies permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_residencies"]))
+)]
+pub async fn move_out(
+  staff: StaffAccess,
+  path: web::Path<Uuid>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  let internal = |what: &'static str| {
+    move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+  };
+  let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+
+  let residency = ResidencyRepository::find_by_id_for_update(&mut *tx, &path.into_inner())
+    .await
+    .map_err(internal("Residency lookup failed"))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Residency not found"))?;
+  let room = RoomRepository::find_by_id_for_update(&mut *tx, &residency.room_id)
+    .await
+    .map_err(internal("Room lookup failed"))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+  staff.require(Permission::ManageResidencies, Scope::building(room.building.as_deref()))?;
+
+  let residency = free_bed(&mut tx, &residency, &room)
+    .await
+    .map_err(internal("Failed to end residency"))?
+    .ok_or_else(|| actix_web::error::ErrorConflict("Residency has already ended"))?;
+
+  // Close the application the student moved in with, so allocation runs
+  // treat them as unhoused again.
+  if let Some(application_id) = residency.application_id {
+    let app = ApplicationRepository::find_by_id_for_update(&mut *tx, &application_id)
+      .await
+      .map_err(internal("Application lookup failed"))?;
+    if let Some(app) = app {
+      let next = match app.status {
+        ApplicationStatus::Approved => Some(ApplicationStatus::Withdrawn),
+        ApplicationStatus::CheckedIn => Some(ApplicationStatus::CheckedOut),
+        _ => None,
+      };
+      if let Some(next) = next {
+        ApplicationRepository::update_status(&mut *tx, &app.id, next, app.comment)
+          .await
+          .map_err(internal("Failed to close application"))?;
+      }
+    }
+  }
+
+  tx.commit().await.map_err(internal("Failed to commit move-out"))?;
+  Ok(HttpResponse::Ok().json(residency))
+}
+
+/// Ends `residency` and gives its bed back to `room`. Both rows must already
+/// be locked by the caller's transaction. Returns `None` if the residency
+/// had already ended.
+pub(crate) async fn free_bed(
+  conn: &mut PgConnection,
+  residency: &Residency,
+  room: &Room,
+) -> Result<Option<Residency>, sqlx::Error> {
+  let Some(residency) = ResidencyRepository::move_out(&mut *conn, &residency.id, Utc::now()).await? else {
+    return Ok(None);
+  };
+
+  let status = if room.status == RoomStatus::Occupied {
+    RoomStatus::Available
+  } else {
+    room.status
+  };
+  RoomRepository::update_occupancy(&mut *conn, &room.id, (room.current_occupants - 1).max(0), status)
+    .await?;
+  Ok(Some(residency))
+}
@@ -1,18 +1,31 @@
 use actix_web::{web, HttpResponse, Responder};
 use dormmatch_common::{
-    models::{application::Application, room::Room},
+    middleware::role::StaffAccess,
+    models::{
+        application::{Application, ApplicationStatus},
+        residency::Residency,
+        role_assignment::{Permission, Scope},
+        room::{Room, RoomStatus},
+    },
     repositories::{
         application::ApplicationRepository,
+        matching_settings::MatchingSettingsRepository,
         profile::{PostgresStudentProfileRepository, StudentProfileRepository},
+        residency::ResidencyRepository,
         room::RoomRepository,
         user::UserRepository,
     },
+    utils::jwt::Claims,
 };
 use serde_json::Value;
 use sqlx::{types::chrono::Utc, PgPool};
 use uuid::Uuid;
+use crate::controllers::residencies::free_bed;
 use crate::services::matching::MatchingService;
-use crate::models::RoomStats;
+use crate::models::{
+    ApplicationQuery, ApplicationStatusRequest, OverviewSort, RoomMatch, RoomOverview, RoomOverviewQuery, RoomStats,
+    RoomStatusRequest, ScoredRoom, SortOrder,
+};
 
 #[utoipa::path(
     post,
@@ -20,11 +33,21 @@ use crate::models::RoomStats;
     request_body(content = Room, content_type = "application/json"),
     responses(
         (status = 201, description = "Room created", body = Room),
-        (status = 400, description = "Invalid input", body = String)
-    )
+        (status = 400, description = "Invalid input", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_rooms permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_rooms"]))
 )]
-pub async fn create_room(room: web::Json<Room>, pool: web::Data<PgPool>) -> impl Responder {
+pub async fn create_room(
+    staff: StaffAccess,
+    room: web::Json<Room>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
     let room = room.into_inner();
+    if let Err(e) = staff.require(Permission::ManageRooms, Scope::building(room.building.as_deref())) {
+        return HttpResponse::from_error(e);
+    }
     match RoomRepository::create(&pool, &room).await {
         Ok(room) => HttpResponse::Created().json(room),
         Err(e) => HttpResponse::BadRequest().body(format!("Failed to create room: {}", e)),
@@ -35,30 +58,36 @@ pub async fn create_room(room: web::Json<Room>, pool: web::Data<PgPool>) -> impl
     get,
     path = "/rooms/search",
     responses(
-        (status = 200, description = "List of available rooms", body = [Room]),
-        (status = 400, description = "Invalid parameters", body = String)
-    )
+        (status = 200, description = "Available rooms, best match first", body = [ScoredRoom]),
+        (status = 400, description = "Invalid parameters", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 409, description = "Student already lives in a room", body = String)
+    ),
+    security(("bearerAuth" = []))
 )]
 pub async fn search_rooms(
     pool: web::Data<PgPool>,
-    user_id: web::ReqData<uuid::Uuid>,
+    claims: web::ReqData<Claims>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let user_id = user_id.into_inner();
+    let user_id = claims
+        .user_id()
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
     let user = UserRepository::find_by_id(&pool, &user_id)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(actix_web::error::ErrorInternalServerError)?;
 
     if user.is_none() {
         return Err(actix_web::error::ErrorNotFound("User not found"));
     }
 
-    let profile = PostgresStudentProfileRepository
-        .get_by_user_id(&pool, &user_id)
+    let profile = PostgresStudentProfileRepository::find_by_user_id(pool.get_ref(), &user_id)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(actix_web::error::ErrorInternalServerError)?;
 
     let profile = profile.ok_or_else(|| actix_web::error::ErrorBadRequest("Profile not found"))?;
 
+    refuse_housed(&pool, &user_id).await?;
+
     let rooms = RoomRepository::find_available(
         &pool,
         Some(&profile.faculty),
@@ -66,9 +95,226 @@ pub async fn search_rooms(
         &profile.gender,
     )
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let room_ids: Vec<Uuid> = rooms.iter().map(|room| room.id).collect();
+    let occupants = PostgresStudentProfileRepository::find_occupants_by_room_ids(&pool, &room_ids)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let settings = MatchingSettingsRepository::find_active(&pool)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorInternalServerError("No active matching settings"))?;
+
+    let ranked: Vec<ScoredRoom> = MatchingService::rank_rooms(&profile, &rooms, &occupants, &settings)
+        .into_iter()
+        .map(|ranked| ScoredRoom {
+            room: ranked.room.clone(),
+            match_score: ranked.score,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ranked))
+}
+
+/// Housed students do not get to pick another room; answers 409 naming the
+/// room they live in.
+async fn refuse_housed(pool: &PgPool, user_id: &Uuid) -> Result<(), actix_web::Error> {
+    let residency = ResidencyRepository::find_active_by_user_id(pool, user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Residency lookup failed: {}", e)))?;
+    let Some(residency) = residency else {
+        return Ok(());
+    };
+    let number = RoomRepository::find_by_id(pool, &residency.room_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+        .map(|room| room.number)
+        .unwrap_or_default();
+    Err(actix_web::error::ErrorConflict(format!(
+        "Student already lives in room {}",
+        number
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/rooms/overview",
+    params(RoomOverviewQuery),
+    responses(
+        (status = 200, description = "Every room with its residents and their pairwise match scores", body = [RoomOverview]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_rooms permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_rooms"]))
+)]
+pub async fn get_rooms_overview(
+    staff: StaffAccess,
+    query: web::Query<RoomOverviewQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let query = query.into_inner();
+
+    let rooms = RoomRepository::find_all(&pool)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get rooms: {}", e)))?
+        .into_iter()
+        .filter(|room| staff.can(Permission::ManageRooms, Scope::building(room.building.as_deref())))
+        .collect::<Vec<_>>();
+
+    let room_ids: Vec<Uuid> = rooms.iter().map(|room| room.id).collect();
+    let mut occupants = PostgresStudentProfileRepository::find_occupants_by_room_ids(&pool, &room_ids)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Occupant lookup failed: {}", e)))?;
+
+    let settings = MatchingSettingsRepository::find_active(&pool)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Settings lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorInternalServerError("No active matching settings"))?;
+
+    let in_range = |value: Option<f64>, min: Option<f64>, max: Option<f64>| {
+        if min.is_none() && max.is_none() {
+            return true;
+        }
+        value.is_some_and(|v| min.is_none_or(|min| v >= min) && max.is_none_or(|max| v <= max))
+    };
+
+    let mut overview: Vec<RoomOverview> = rooms
+        .into_iter()
+        .map(|room| {
+            let residents = occupants.remove(&room.id).unwrap_or_default();
+            let pair_scores = MatchingService::pairwise_scores(&residents, &settings);
+            let pairs: Vec<f64> = pair_scores
+                .iter()
+                .enumerate()
+                .flat_map(|(i, row)| row.iter().skip(i + 1).flatten().copied())
+                .collect();
+            RoomOverview {
+                room,
+                residents,
+                mean_pair_score: (!pairs.is_empty()).then(|| pairs.iter().sum::<f64>() / pairs.len() as f64),
+                min_pair_score: pairs.iter().copied().reduce(f64::min),
+                pair_scores,
+            }
+        })
+        .filter(|room| {
+            in_range(room.mean_pair_score, query.min_mean_score, query.max_mean_score)
+                && in_range(room.min_pair_score, query.min_min_score, query.max_min_score)
+        })
+        .collect();
+
+    let descending = matches!(query.order, Some(SortOrder::Desc));
+    let by_score = |a: Option<f64>, b: Option<f64>| match (a, b) {
+        (Some(a), Some(b)) if descending => b.total_cmp(&a),
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    };
+    match query.sort_by.unwrap_or(OverviewSort::Number) {
+        OverviewSort::Number if descending => overview.sort_by(|a, b| b.room.number.cmp(&a.room.number)),
+        OverviewSort::Number => {}
+        OverviewSort::MeanPairScore => {
+            overview.sort_by(|a, b| by_score(a.mean_pair_score, b.mean_pair_score))
+        }
+        OverviewSort::MinPairScore => {
+            overview.sort_by(|a, b| by_score(a.min_pair_score, b.min_pair_score))
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(overview))
+}
+
+#[utoipa::path(
+    get,
+    path = "/rooms/{id}/match",
+    params(
+        ("id", Path, description = "Room ID")
+    ),
+    responses(
+        (status = 200, description = "Per-criterion match breakdown for the caller", body = RoomMatch),
+        (status = 400, description = "Profile not found", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 404, description = "Room not found", body = String)
+    ),
+    security(("bearerAuth" = []))
+)]
+pub async fn get_room_match(
+    path: web::Path<Uuid>,
+    claims: web::ReqData<Claims>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = claims
+        .user_id()
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
+
+    let room = RoomRepository::find_by_id(&pool, &path.into_inner())
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
 
-    Ok(HttpResponse::Ok().json(rooms))
+    Ok(HttpResponse::Ok().json(room_match(&pool, &room, user_id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/rooms/{id}/match/{user_id}",
+    params(
+        ("id", Path, description = "Room ID"),
+        ("user_id", Path, description = "Student user ID")
+    ),
+    responses(
+        (status = 200, description = "Per-criterion match breakdown for the student", body = RoomMatch),
+        (status = 400, description = "Profile not found", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the review_applications permission for the room's building", body = String),
+        (status = 404, description = "Room not found", body = String)
+    ),
+    security(("bearerAuth" = ["review_applications"]))
+)]
+pub async fn get_student_room_match(
+    staff: StaffAccess,
+    path: web::Path<(Uuid, Uuid)>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (room_id, user_id) = path.into_inner();
+
+    let room = RoomRepository::find_by_id(&pool, &room_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+    staff.require(Permission::ReviewApplications, Scope::building(room.building.as_deref()))?;
+
+    Ok(HttpResponse::Ok().json(room_match(&pool, &room, user_id).await?))
+}
+
+/// Explains how well the student fits with the room's current occupants.
+async fn room_match(pool: &PgPool, room: &Room, user_id: Uuid) -> Result<RoomMatch, actix_web::Error> {
+    let profile = PostgresStudentProfileRepository::find_by_user_id(pool, &user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Profile lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("Profile not found"))?;
+
+    let occupants = PostgresStudentProfileRepository::find_occupants_by_room_ids(pool, &[room.id])
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Occupant lookup failed: {}", e)))?;
+
+    let settings = MatchingSettingsRepository::find_active(pool)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Settings lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorInternalServerError("No active matching settings"))?;
+
+    let breakdown = MatchingService::explain(
+        &profile,
+        occupants.get(&room.id).map(Vec::as_slice).unwrap_or_default(),
+        &settings,
+    );
+
+    Ok(RoomMatch {
+        room_id: room.id,
+        user_id,
+        compatible: MatchingService::is_compatible(room, &profile),
+        breakdown,
+    })
 }
 
 #[utoipa::path(
@@ -76,33 +322,38 @@ pub async fn search_rooms(
     path = "/rooms/apply",
     request_body(content = Value, content_type = "application/json"),
     responses(
-        (status = 201, description = "Application submitted", body = Application),
-        (status = 400, description = "Invalid input", body = String)
-    )
+        (status = 201, description = "Application submitted for the caller", body = Application),
+        (status = 400, description = "Invalid input", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 409, description = "Student already lives in a room", body = String)
+    ),
+    security(("bearerAuth" = []))
 )]
 pub async fn apply_room(
+    claims: web::ReqData<Claims>,
     body: web::Json<Value>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let user_id = body
-        .get("user_id")
-        .and_then(|v| v.as_str())
-        .and_then(|s| Uuid::parse_str(s).ok())
-        .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid user_id"))?;
+    let user_id = claims
+        .user_id()
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
 
     let room_id = body
         .get("room_id")
         .and_then(|v| v.as_str())
         .and_then(|s| Uuid::parse_str(s).ok())
         .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid room_id"))?;
+    refuse_housed(&pool, &user_id).await?;
 
     let application = Application {
         id: Uuid::new_v4(),
         user_id,
         room_id,
-        status: "pending".to_string(),
+        status: ApplicationStatus::Pending,
         comment: None,
         created_at: Utc::now(),
+        matching_settings_version: None,
+        allocation_run_id: None,
     };
 
     let app = ApplicationRepository::create(&pool, &application)
@@ -115,23 +366,19 @@ pub async fn apply_room(
 #[utoipa::path(
     get,
     path = "/rooms/applications",
-    params(
-        ("user_id", Query, description = "User ID")
-    ),
     responses(
-        (status = 200, description = "List of applications", body = [Application]),
-        (status = 400, description = "Invalid user_id", body = String)
-    )
+        (status = 200, description = "The caller's applications", body = [Application]),
+        (status = 401, description = "Missing or invalid token", body = String)
+    ),
+    security(("bearerAuth" = []))
 )]
 pub async fn get_applications(
-    query: web::Query<Value>,
+    claims: web::ReqData<Claims>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let user_id = query
-        .get("user_id")
-        .and_then(|v| v.as_str())
-        .and_then(|s| Uuid::parse_str(s).ok())
-        .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid user_id"))?;
+    let user_id = claims
+        .user_id()
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
 
     let apps = ApplicationRepository::find_by_user_id(&pool, &user_id)
         .await
@@ -140,6 +387,47 @@ pub async fn get_applications(
     Ok(HttpResponse::Ok().json(apps))
 }
 
+#[utoipa::path(
+    get,
+    path = "/rooms/applications/all",
+    params(ApplicationQuery),
+    responses(
+        (status = 200, description = "Applications for rooms the caller reviews, oldest first", body = [Application]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the review_applications permission for the building", body = String)
+    ),
+    security(("bearerAuth" = ["review_applications"]))
+)]
+pub async fn list_applications(
+    staff: StaffAccess,
+    query: web::Query<ApplicationQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let query = query.into_inner();
+    if let Some(building) = &query.building {
+        staff.require(Permission::ReviewApplications, Scope::building(Some(building)))?;
+    }
+
+    let room_ids: Vec<Uuid> = RoomRepository::find_all(&pool)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get rooms: {}", e)))?
+        .into_iter()
+        .filter(|room| query.building.is_none() || room.building == query.building)
+        .filter(|room| staff.can(Permission::ReviewApplications, Scope::building(room.building.as_deref())))
+        .map(|room| room.id)
+        .collect();
+    let statuses = match query.status {
+        Some(status) => vec![status],
+        None => ApplicationStatus::ALL.to_vec(),
+    };
+
+    let apps = ApplicationRepository::find_by_room_ids(&pool, &room_ids, &statuses)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to get applications: {}", e)))?;
+
+    Ok(HttpResponse::Ok().json(apps))
+}
+
 #[utoipa::path(
     post,
     path = "/rooms/applications/{id}/approve",
@@ -148,25 +436,161 @@ pub async fn get_applications(
     ),
     request_body(content = Value, content_type = "application/json"),
     responses(
-        (status = 200, description = "Application approved", body = Application),
-        (status = 400, description = "Invalid input", body = String)
-    )
+        (status = 200, description = "Application approved and student moved in", body = Application),
+        (status = 404, description = "Application or room not found", body = String),
+        (status = 409, description = "Application is not pending, the student is already housed or the room cannot take them", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the review_applications permission", body = String)
+    ),
+    security(("bearerAuth" = ["review_applications"]))
 )]
 pub async fn approve_application(
+    staff: StaffAccess,
     path: web::Path<Uuid>,
     body: web::Json<Value>,
     pool: web::Data<PgPool>,
-) -> impl Responder {
-    let id = path.into_inner();
+) -> Result<HttpResponse, actix_web::Error> {
     let comment = body
         .get("comment")
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    match ApplicationRepository::update_status(&pool, &id, "approved", comment).await {
-        Ok(app) => HttpResponse::Ok().json(app),
-        Err(e) => HttpResponse::BadRequest().body(format!("Failed to approve: {}", e)),
+    change_application_status(&staff, &pool, &path.into_inner(), ApplicationStatus::Approved, comment).await
+}
+
+/// Approves a pending application and moves the student in, all in one
+/// transaction. The room row stays locked until the end, so concurrent
+/// approvals for the same room see each other's occupants and cannot
+/// overfill it.
+async fn approve(
+    pool: &PgPool,
+    id: &Uuid,
+    comment: Option<String>,
+) -> Result<Application, actix_web::Error> {
+    let internal = |what: &'static str| {
+        move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+    };
+    let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+
+    let app = ApplicationRepository::find_by_id_for_update(&mut *tx, id)
+        .await
+        .map_err(internal("Application lookup failed"))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Application not found"))?;
+    if !app.status.can_transition_to(ApplicationStatus::Approved) {
+        return Err(actix_web::error::ErrorConflict(format!(
+            "Cannot move application from {:?} to {:?}",
+            app.status,
+            ApplicationStatus::Approved
+        )));
     }
+
+    let room = RoomRepository::find_by_id_for_update(&mut *tx, &app.room_id)
+        .await
+        .map_err(internal("Room lookup failed"))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+    let profile = PostgresStudentProfileRepository::find_by_user_id(&mut *tx, &app.user_id)
+        .await
+        .map_err(internal("Profile lookup failed"))?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("Profile not found"))?;
+
+    let housed = ResidencyRepository::find_active_by_user_id(&mut *tx, &app.user_id)
+        .await
+        .map_err(internal("Residency lookup failed"))?;
+    if housed.is_some() {
+        return Err(actix_web::error::ErrorConflict("Student already lives in a room"));
+    }
+    if !MatchingService::is_compatible(&room, &profile) {
+        return Err(actix_web::error::ErrorConflict(format!(
+            "Room {} can no longer take this student",
+            room.number
+        )));
+    }
+
+    let now = Utc::now();
+    ResidencyRepository::create(
+        &mut *tx,
+        &Residency {
+            id: Uuid::new_v4(),
+            user_id: app.user_id,
+            room_id: room.id,
+            application_id: Some(app.id),
+            moved_in_at: now,
+            moved_out_at: None,
+        },
+    )
+    .await
+    .map_err(internal("Failed to record residency"))?;
+
+    let occupants = room.current_occupants + 1;
+    let status = if occupants >= room.capacity {
+        RoomStatus::Occupied
+    } else {
+        room.status
+    };
+    RoomRepository::update_occupancy(&mut *tx, &room.id, occupants, status)
+        .await
+        .map_err(internal("Failed to update room"))?;
+
+    let app = ApplicationRepository::update_status(&mut *tx, id, ApplicationStatus::Approved, comment)
+        .await
+        .map_err(internal("Failed to approve"))?
+        .ok_or_else(|| actix_web::error::ErrorConflict("Application changed during approval"))?;
+
+    tx.commit().await.map_err(internal("Failed to commit approval"))?;
+    Ok(app)
+}
+
+/// Withdraws or checks out an application. If the student moved in through
+/// it, the residency ends and the bed is freed in the same transaction.
+/// Rows are locked residency first, then room, then application, the same
+/// order as a move-out.
+async fn release(
+    pool: &PgPool,
+    id: &Uuid,
+    status: ApplicationStatus,
+    comment: Option<String>,
+) -> Result<Application, actix_web::Error> {
+    let internal = |what: &'static str| {
+        move |e: sqlx::Error| actix_web::error::ErrorInternalServerError(format!("{}: {}", what, e))
+    };
+    let mut tx = pool.begin().await.map_err(internal("Failed to start transaction"))?;
+
+    let residency = ResidencyRepository::find_active_by_application_id(&mut *tx, id)
+        .await
+        .map_err(internal("Residency lookup failed"))?;
+    if let Some(residency) = residency {
+        let residency = ResidencyRepository::find_by_id_for_update(&mut *tx, &residency.id)
+            .await
+            .map_err(internal("Residency lookup failed"))?
+            .ok_or_else(|| actix_web::error::ErrorNotFound("Residency not found"))?;
+        let room = RoomRepository::find_by_id_for_update(&mut *tx, &residency.room_id)
+            .await
+            .map_err(internal("Room lookup failed"))?
+            .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+        // A concurrent move-out may have ended it already; the status check
+        // below then refuses the transition.
+        free_bed(&mut tx, &residency, &room)
+            .await
+            .map_err(internal("Failed to end residency"))?;
+    }
+
+    let app = ApplicationRepository::find_by_id_for_update(&mut *tx, id)
+        .await
+        .map_err(internal("Application lookup failed"))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Application not found"))?;
+    if !app.status.can_transition_to(status) {
+        return Err(actix_web::error::ErrorConflict(format!(
+            "Cannot move application from {:?} to {:?}",
+            app.status, status
+        )));
+    }
+    let app = ApplicationRepository::update_status(&mut *tx, id, status, comment)
+        .await
+        .map_err(internal("Failed to update application"))?
+        .ok_or_else(|| actix_web::error::ErrorConflict("Application changed during update"))?;
+
+    tx.commit().await.map_err(internal("Failed to commit status change"))?;
+    Ok(app)
 }
 
 #[utoipa::path(
@@ -178,34 +602,166 @@ pub async fn approve_application(
     request_body(content = Value, content_type = "application/json"),
     responses(
         (status = 200, description = "Application rejected", body = Application),
-        (status = 400, description = "Invalid input", body = String)
-    )
+        (status = 404, description = "Application not found", body = String),
+        (status = 409, description = "Application is not pending", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the review_applications permission", body = String)
+    ),
+    security(("bearerAuth" = ["review_applications"]))
 )]
 pub async fn reject_application(
+    staff: StaffAccess,
     path: web::Path<Uuid>,
     body: web::Json<Value>,
     pool: web::Data<PgPool>,
-) -> impl Responder {
-    let id = path.into_inner();
+) -> Result<HttpResponse, actix_web::Error> {
     let comment = body
         .get("comment")
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    match ApplicationRepository::update_status(&pool, &id, "rejected", comment).await {
-        Ok(app) => HttpResponse::Ok().json(app),
-        Err(e) => HttpResponse::BadRequest().body(format!("Failed to reject: {}", e)),
+    change_application_status(&staff, &pool, &path.into_inner(), ApplicationStatus::Rejected, comment).await
+}
+
+#[utoipa::path(
+    put,
+    path = "/rooms/applications/{id}/status",
+    params(
+        ("id", Path, description = "Application ID")
+    ),
+    request_body = ApplicationStatusRequest,
+    responses(
+        (status = 200, description = "Application status changed; withdrawing or checking out also ends the residency and frees the bed", body = Application),
+        (status = 404, description = "Application not found", body = String),
+        (status = 409, description = "Transition not allowed from the current status", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the review_applications permission", body = String)
+    ),
+    security(("bearerAuth" = ["review_applications"]))
+)]
+pub async fn set_application_status(
+    staff: StaffAccess,
+    path: web::Path<Uuid>,
+    body: web::Json<ApplicationStatusRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let body = body.into_inner();
+    change_application_status(&staff, &pool, &path.into_inner(), body.status, body.comment).await
+}
+
+/// Applies a status transition, answering 403 when the caller may not review
+/// applications for the room and 409 when the application's current status
+/// does not allow it.
+async fn change_application_status(
+    staff: &StaffAccess,
+    pool: &PgPool,
+    id: &Uuid,
+    status: ApplicationStatus,
+    comment: Option<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let app = ApplicationRepository::find_by_id(pool, id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Application lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Application not found"))?;
+    let room = RoomRepository::find_by_id(pool, &app.room_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+    staff.require(Permission::ReviewApplications, Scope::building(room.building.as_deref()))?;
+
+    match status {
+        ApplicationStatus::Approved => {
+            let app = approve(pool, id, comment).await?;
+            return Ok(HttpResponse::Ok().json(app));
+        }
+        ApplicationStatus::Withdrawn | ApplicationStatus::CheckedOut => {
+            let app = release(pool, id, status, comment).await?;
+            return Ok(HttpResponse::Ok().json(app));
+        }
+        _ => {}
     }
+
+    let updated = ApplicationRepository::update_status(pool, id, status, comment)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to update application: {}", e)))?;
+    if let Some(app) = updated {
+        return Ok(HttpResponse::Ok().json(app));
+    }
+
+    let current = ApplicationRepository::find_by_id(pool, id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Application lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Application not found"))?;
+    Err(actix_web::error::ErrorConflict(format!(
+        "Cannot move application from {:?} to {:?}",
+        current.status, status
+    )))
+}
+
+#[utoipa::path(
+    put,
+    path = "/rooms/{id}/status",
+    params(
+        ("id", Path, description = "Room ID")
+    ),
+    request_body = RoomStatusRequest,
+    responses(
+        (status = 200, description = "Room status changed", body = Room),
+        (status = 404, description = "Room not found", body = String),
+        (status = 409, description = "Transition not allowed from the current status", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_rooms permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_rooms"]))
+)]
+pub async fn set_room_status(
+    staff: StaffAccess,
+    path: web::Path<Uuid>,
+    body: web::Json<RoomStatusRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = path.into_inner();
+    let status = body.into_inner().status;
+
+    let room = RoomRepository::find_by_id(&pool, &id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+    staff.require(Permission::ManageRooms, Scope::building(room.building.as_deref()))?;
+
+    let updated = RoomRepository::update_status(&pool, &id, status)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to update room: {}", e)))?;
+    if let Some(room) = updated {
+        return Ok(HttpResponse::Ok().json(room));
+    }
+
+    let current = RoomRepository::find_by_id(&pool, &id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Room not found"))?;
+    Err(actix_web::error::ErrorConflict(format!(
+        "Cannot move room from {:?} to {:?}",
+        current.status, status
+    )))
 }
 
 #[utoipa::path(
     get,
     path = "/rooms/stats",
     responses(
-        (status = 200, description = "Statistics", body = RoomStats)
-    )
+        (status = 200, description = "Statistics", body = RoomStats),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_rooms permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_rooms"]))
 )]
-pub async fn get_stats(pool: web::Data<PgPool>) -> Result<HttpResponse, actix_web::Error> {
+pub async fn get_stats(
+    staff: StaffAccess,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    staff.require(Permission::ManageRooms, Scope::global())?;
+
     let stats = sqlx::query!(
         r#"
         SELECT
@@ -231,29 +787,29 @@ pub async fn get_stats(pool: web::Data<PgPool>) -> Result<HttpResponse, actix_we
 #[utoipa::path(
     post,
     path = "/rooms/auto-assign",
-    request_body(content = Value, content_type = "application/json"),
     responses(
-        (status = 200, description = "Auto-assigned room", body = Application),
-        (status = 400, description = "No suitable room found", body = String)
-    )
+        (status = 200, description = "Application for the caller's best matching room", body = Application),
+        (status = 400, description = "No suitable room found", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 409, description = "Student already lives in a room", body = String)
+    ),
+    security(("bearerAuth" = []))
 )]
 pub async fn auto_assign(
-    body: web::Json<Value>,
+    claims: web::ReqData<Claims>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let user_id = body
-        .get("user_id")
-        .and_then(|v| v.as_str())
-        .and_then(|s| Uuid::parse_str(s).ok())
-        .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid user_id"))?;
+    let user_id = claims
+        .user_id()
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid token subject"))?;
 
     let user = UserRepository::find_by_id(&pool, &user_id)
         .await
         .map_err(|e| actix_web::error::ErrorInternalServerError(format!("User lookup failed: {}", e)))?
         .ok_or_else(|| actix_web::error::ErrorNotFound("User not found"))?;
+    refuse_housed(&pool, &user.id).await?;
 
-    let profile = PostgresStudentProfileRepository
-        .get_by_user_id(&pool, &user.id)
+    let profile = PostgresStudentProfileRepository::find_by_user_id(pool.get_ref(), &user.id)
         .await
         .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Profile lookup failed: {}", e)))?
         .ok_or_else(|| actix_web::error::ErrorBadRequest("Profile not found"))?;
@@ -267,16 +823,28 @@ pub async fn auto_assign(
     .await
     .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Room search failed: {}", e)))?;
 
-    let matching_room = MatchingService::find_best_room(&profile, &rooms)
+    let room_ids: Vec<Uuid> = rooms.iter().map(|room| room.id).collect();
+    let occupants = PostgresStudentProfileRepository::find_occupants_by_room_ids(&pool, &room_ids)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Occupant lookup failed: {}", e)))?;
+
+    let settings = MatchingSettingsRepository::find_active(&pool)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Settings lookup failed: {}", e)))?
+        .ok_or_else(|| actix_web::error::ErrorInternalServerError("No active matching settings"))?;
+
+    let best = MatchingService::find_best_room(&profile, &rooms, &occupants, &settings)
         .ok_or_else(|| actix_web::error::ErrorBadRequest("No suitable room found"))?;
 
     let application = Application {
         id: Uuid::new_v4(),
         user_id,
-        room_id: matching_room.id,
-        status: "pending".to_string(),
-        comment: Some("Auto-assigned".to_string()),
+        room_id: best.room.id,
+        status: ApplicationStatus::Pending,
+        comment: Some(format!("Auto-assigned (match {:.0}%)", best.score)),
         created_at: Utc::now(),
+        matching_settings_version: Some(settings.version),
+        allocation_run_id: None,
     };
 
     let app = ApplicationRepository::create(&pool, &application)
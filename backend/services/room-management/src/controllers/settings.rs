@@ -0,0 +1,282 @@
+use actix_web::{web, HttpResponse};
+use dormmatch_common::{
+  middleware::role::StaffAccess,
+  models::{
+    matching_settings::MatchingSettings,
+    role_assignment::{Permission, Scope},
+  },
+  repositories::matching_settings::{MatchingSettingsRepository, MatchingWeights},
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct MatchingSettingsRequest {
+  wake_hours_weight: f64,
+  mbti_weight: f64,
+  hobbies_weight: f64,
+  age_weight: f64,
+  faculty_weight: f64,
+  course_weight: f64,
+  /// Minimum acceptable match percentage, 0–100.
+  min_score: f64,
+}
+
+impl MatchingSettingsRequest {
+  fn into_weights(self) -> Result<MatchingWeights, actix_web::Error> {
+    let weights = [
+      self.wake_hours_weight,
+      self.mbti_weight,
+      self.hobbies_weight,
+      self.age_weight,
+      self.faculty_weight,
+      self.course_weight,
+    ];
+    if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+      return Err(actix_web::error::ErrorBadRequest(
+        "Weights must be non-negative numbers",
+      ));
+    }
+    if weights.iter().all(|w| *w == 0.0) {
+      return Err(actix_web::error::ErrorBadRequest(
+        "At least one weight must be positive",
+      ));
+    }
+    if !(0.0..=100.0).contains(&self.min_score) {
+      return Err(actix_web::error::ErrorBadRequest(
+        "min_score must be between 0 and 100",
+      ));
+    }
+
+    Ok(MatchingWeights {
+      wake_hours_weight: self.wake_hours_weight,
+      mbti_weight: self.mbti_weight,
+      hobbies_weight: self.hobbies_weight,
+      age_weight: self.age_weight,
+      faculty_weight: self.faculty_weight,
+      course_weight: self.course_weight,
+      min_score: self.min_score,
+    })
+  }
+}
+
+/// Tells "no such version" apart from "version is locked because it has
+/// been active".
+async fn missing_or_locked(pool: &PgPool, version: i32) -> actix_web::Error {
+  match MatchingSettingsRepository::find_by_version(pool, version).await {
+    Ok(Some(_)) => actix_web::error::ErrorConflict(
+      "Settings that have been active cannot be changed; create a new version instead",
+    ),
+    Ok(None) => actix_web::error::ErrorNotFound("Settings version not found"),
+    Err(e) => {
+      actix_web::error::ErrorInternalServerError(format!("Settings lookup failed: {}", e))
+    }
+  }
+}
+
+#[utoipa::path(
+    get,
+    path = "/matching-settings",
+    responses(
+        (status = 200, description = "All settings versions, newest first", body = [MatchingSettings]),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_settings permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_settings"]))
+)]
+pub async fn list_settings(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageSettings, Scope::global())?;
+
+  let settings = MatchingSettingsRepository::find_all(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to list settings: {}", e)))?;
+
+  Ok(HttpResponse::Ok().json(settings))
+}
+
+#[utoipa::path(
+    get,
+    path = "/matching-settings/active",
+    responses(
+        (status = 200, description = "Settings currently used for scoring", body = MatchingSettings),
+        (status = 404, description = "No active settings", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_settings permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_settings"]))
+)]
+pub async fn get_active_settings(
+  staff: StaffAccess,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageSettings, Scope::global())?;
+
+  let settings = MatchingSettingsRepository::find_active(&pool)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Settings lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("No active matching settings"))?;
+
+  Ok(HttpResponse::Ok().json(settings))
+}
+
+#[utoipa::path(
+    get,
+    path = "/matching-settings/{version}",
+    params(
+        ("version", Path, description = "Settings version")
+    ),
+    responses(
+        (status = 200, description = "Settings version", body = MatchingSettings),
+        (status = 404, description = "Settings version not found", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_settings permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_settings"]))
+)]
+pub async fn get_settings(
+  staff: StaffAccess,
+  path: web::Path<i32>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageSettings, Scope::global())?;
+
+  let settings = MatchingSettingsRepository::find_by_version(&pool, path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Settings lookup failed: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Settings version not found"))?;
+
+  Ok(HttpResponse::Ok().json(settings))
+}
+
+#[utoipa::path(
+    post,
+    path = "/matching-settings",
+    request_body = MatchingSettingsRequest,
+    responses(
+        (status = 201, description = "Inactive settings version created", body = MatchingSettings),
+        (status = 400, description = "Invalid weights", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_settings permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_settings"]))
+)]
+pub async fn create_settings(
+  staff: StaffAccess,
+  req: web::Json<MatchingSettingsRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageSettings, Scope::global())?;
+
+  let weights = req.into_inner().into_weights()?;
+
+  let settings = MatchingSettingsRepository::create(&pool, &weights)
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to create settings: {}", e)))?;
+
+  Ok(HttpResponse::Created().json(settings))
+}
+
+#[utoipa::path(
+    put,
+    path = "/matching-settings/{version}",
+    params(
+        ("version", Path, description = "Settings version")
+    ),
+    request_body = MatchingSettingsRequest,
+    responses(
+        (status = 200, description = "Settings version updated", body = MatchingSettings),
+        (status = 400, description = "Invalid weights", body = String),
+        (status = 404, description = "Settings version not found", body = String),
+        (status = 409, description = "Version has already been active", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_settings permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_settings"]))
+)]
+pub async fn update_settings(
+  staff: StaffAccess,
+  path: web::Path<i32>,
+  req: web::Json<MatchingSettingsRequest>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageSettings, Scope::global())?;
+
+  let version = path.into_inner();
+  let weights = req.into_inner().into_weights()?;
+
+  match MatchingSettingsRepository::update(&pool, version, &weights).await {
+    Ok(Some(settings)) => Ok(HttpResponse::Ok().json(settings)),
+    Ok(None) => Err(missing_or_locked(&pool, version).await),
+    Err(e) => Err(actix_web::error::ErrorInternalServerError(format!(
+      "Failed to update settings: {}",
+      e
+    ))),
+  }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/matching-settings/{version}",
+    params(
+        ("version", Path, description = "Settings version")
+    ),
+    responses(
+        (status = 204, description = "Settings version deleted"),
+        (status = 404, description = "Settings version not found", body = String),
+        (status = 409, description = "Version has already been active", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_settings permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_settings"]))
+)]
+pub async fn delete_settings(
+  staff: StaffAccess,
+  path: web::Path<i32>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageSettings, Scope::global())?;
+
+  let version = path.into_inner();
+
+  match MatchingSettingsRepository::delete(&pool, version).await {
+    Ok(true) => Ok(HttpResponse::NoContent().finish()),
+    Ok(false) => Err(missing_or_locked(&pool, version).await),
+    Err(e) => Err(actix_web::error::ErrorInternalServerError(format!(
+      "Failed to delete settings: {}",
+      e
+    ))),
+  }
+}
+
+#[utoipa::path(
+    post,
+    path = "/matching-settings/{version}/activate",
+    params(
+        ("version", Path, description = "Settings version")
+    ),
+    responses(
+        (status = 200, description = "Settings version is now used for scoring", body = MatchingSettings),
+        (status = 404, description = "Settings version not found", body = String),
+        (status = 401, description = "Missing or invalid token", body = String),
+        (status = 403, description = "Caller lacks the manage_settings permission", body = String)
+    ),
+    security(("bearerAuth" = ["manage_settings"]))
+)]
+pub async fn activate_settings(
+  staff: StaffAccess,
+  path: web::Path<i32>,
+  pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+  staff.require(Permission::ManageSettings, Scope::global())?;
+
+  let settings = MatchingSettingsRepository::activate(&pool, path.into_inner())
+    .await
+    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to activate settings: {}", e)))?
+    .ok_or_else(|| actix_web::error::ErrorNotFound("Settings version not found"))?;
+
+  Ok(HttpResponse::Ok().json(settings))
+}
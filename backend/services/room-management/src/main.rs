@@ -1,5 +1,6 @@
 use actix_web::{web, App, HttpServer};
-use dormmatch_common::config::env::Config;
+use actix_web_httpauth::middleware::HttpAuthentication;
+use dormmatch_common::{config::env::Config, middleware::jwt::jwt_middleware, utils::jwt::JwtKeys};
 use sqlx::PgPool;
 
 mod controllers;
@@ -13,19 +14,44 @@ async fn main() -> std::io::Result<()> {
   let pool = PgPool::connect(&config.database_url)
     .await
     .expect("Failed to connect to database");
+  let redis = redis::Client::open(config.redis_url.as_str()).expect("Invalid Redis URL");
+  let port = config.port_room_management;
+  // Only public keys are needed here; tokens are issued by the auth service.
+  let keys = web::Data::new(JwtKeys::from_config(&config).expect("Invalid JWT key configuration"));
 
   HttpServer::new(move || {
     App::new()
       .app_data(web::Data::new(pool.clone()))
+      .app_data(web::Data::new(redis.clone()))
+      .app_data(web::Data::new(config.clone()))
+      .app_data(keys.clone())
       .service(
         web::scope("/rooms")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
           .route("", web::post().to(controllers::rooms::create_room))
           .route("/search", web::get().to(controllers::rooms::search_rooms))
+          .route("/overview", web::get().to(controllers::rooms::get_rooms_overview))
+          .route("/{id}/match", web::get().to(controllers::rooms::get_room_match))
+          .route(
+            "/{id}/match/{user_id}",
+            web::get().to(controllers::rooms::get_student_room_match),
+          )
+          .route("/{id}/status", web::put().to(controllers::rooms::set_room_status))
+          .route(
+            "/{id}/residents",
+            web::get().to(controllers::residencies::get_room_residents),
+          )
+          .service(
+            web::resource("/preferences")
+              .route(web::get().to(controllers::preferences::get_preferences))
+              .route(web::put().to(controllers::preferences::set_preferences)),
+          )
           .route("/apply", web::post().to(controllers::rooms::apply_room))
           .service(
             web::resource("/applications")
               .route(web::get().to(controllers::rooms::get_applications)),
           )
+          .route("/applications/all", web::get().to(controllers::rooms::list_applications))
           .route("/stats", web::get().to(controllers::rooms::get_stats))
           .service(
             web::resource("/auto-assign").route(web::post().to(controllers::rooms::auto_assign)),
@@ -37,11 +63,73 @@ async fn main() -> std::io::Result<()> {
           .service(
             web::resource("/applications/{id}/reject")
               .route(web::post().to(controllers::rooms::reject_application)),
+          )
+          .service(
+            web::resource("/applications/{id}/status")
+              .route(web::put().to(controllers::rooms::set_application_status)),
+          ),
+      )
+      .service(
+        web::scope("/allocations")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route("", web::get().to(controllers::allocations::list_allocation_runs))
+          .route(
+            "/batch",
+            web::post().to(controllers::allocations::run_batch_allocation),
+          )
+          .route(
+            "/stable-roommates",
+            web::post().to(controllers::allocations::run_stable_roommates_allocation),
+          )
+          .route(
+            "/deferred-acceptance",
+            web::post().to(controllers::allocations::run_deferred_acceptance),
+          )
+          .service(
+            web::resource("/lotteries")
+              .route(web::get().to(controllers::allocations::list_lotteries))
+              .route(web::post().to(controllers::allocations::announce_lottery)),
+          )
+          .route("/lotteries/{id}", web::get().to(controllers::allocations::get_lottery))
+          .route(
+            "/lotteries/{id}/draw",
+            web::post().to(controllers::allocations::draw_lottery),
+          )
+          .service(
+            web::resource("/benefits/{user_id}")
+              .route(web::put().to(controllers::allocations::set_social_benefit))
+              .route(web::delete().to(controllers::allocations::remove_social_benefit)),
+          ),
+      )
+      .service(
+        web::scope("/residencies")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route(
+            "/current",
+            web::get().to(controllers::residencies::get_current_residency),
+          )
+          .route(
+            "/{id}/move-out",
+            web::post().to(controllers::residencies::move_out),
+          ),
+      )
+      .service(
+        web::scope("/matching-settings")
+          .wrap(HttpAuthentication::bearer(jwt_middleware))
+          .route("", web::get().to(controllers::settings::list_settings))
+          .route("", web::post().to(controllers::settings::create_settings))
+          .route("/active", web::get().to(controllers::settings::get_active_settings))
+          .route("/{version}", web::get().to(controllers::settings::get_settings))
+          .route("/{version}", web::put().to(controllers::settings::update_settings))
+          .route("/{version}", web::delete().to(controllers::settings::delete_settings))
+          .route(
+            "/{version}/activate",
+            web::post().to(controllers::settings::activate_settings),
           ),
       )
       .configure(openapi::configure_openapi)
   })
-  .bind(("0.0.0.0", config.port_room_management))?
+  .bind(("0.0.0.0", port))?
   .run()
   .await
 }
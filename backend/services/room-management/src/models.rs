@@ -1,5 +1,14 @@
+use dormmatch_common::models::{
+  application::ApplicationStatus,
+  lottery::Lottery,
+  profile::StudentProfile,
+  residency::Residency,
+  room::{Room, RoomStatus},
+};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::services::matching::Criterion;
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct RoomStats {
@@ -8,3 +17,265 @@ pub struct RoomStats {
   pub reserved_rooms: i64,
   pub pending_applications: i64,
 }
+
+#[derive(Serialize, ToSchema)]
+pub struct ScoredRoom {
+  #[serde(flatten)]
+  pub room: Room,
+  /// Compatibility with the current occupants, 0–100.
+  pub match_score: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CriterionScore {
+  pub criterion: Criterion,
+  pub weight: f64,
+  /// Similarity in `[0, 1]` averaged over the occupants, or `null` when the
+  /// criterion could not be evaluated (e.g. nobody filled in their MBTI).
+  pub similarity: Option<f64>,
+  /// Points this criterion adds to the final score.
+  pub contribution: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MatchBreakdown {
+  pub score: f64,
+  /// Number of occupants the student was compared against.
+  pub occupants: usize,
+  pub criteria: Vec<CriterionScore>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RoomMatch {
+  pub room_id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  /// Whether the room's sex, faculty, course and capacity restrictions admit
+  /// the student at all.
+  pub compatible: bool,
+  #[serde(flatten)]
+  pub breakdown: MatchBreakdown,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AllocationRequest {
+  /// Only compute the plan without creating applications. Defaults to true.
+  pub dry_run: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema, Clone)]
+pub struct PlannedPlacement {
+  pub user_id: uuid::Uuid,
+  pub room_id: uuid::Uuid,
+  pub room_number: String,
+  /// Compatibility with everyone the student would share the room with.
+  pub score: f64,
+  /// How the student ended up in this room, for modes that can explain it.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Clone)]
+pub struct UnplacedStudent {
+  pub user_id: uuid::Uuid,
+  pub reason: String,
+  /// Room a rejected application is recorded against when the plan is
+  /// committed, if the mode records one.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub room_id: Option<uuid::Uuid>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PlanMetrics {
+  pub students: usize,
+  pub rooms: usize,
+  pub assigned: usize,
+  pub unassigned: usize,
+  pub total_score: f64,
+  pub mean_score: Option<f64>,
+  pub min_score: Option<f64>,
+  /// Placements whose final score ended up below the settings' minimum.
+  pub below_min_score: usize,
+}
+
+impl PlanMetrics {
+  pub fn new(
+    students: usize,
+    rooms: usize,
+    placements: &[PlannedPlacement],
+    min_acceptable: f64,
+  ) -> Self {
+    let total_score: f64 = placements.iter().map(|p| p.score).sum();
+    PlanMetrics {
+      students,
+      rooms,
+      assigned: placements.len(),
+      unassigned: students - placements.len(),
+      total_score,
+      mean_score: (!placements.is_empty()).then(|| total_score / placements.len() as f64),
+      min_score: placements.iter().map(|p| p.score).min_by(f64::total_cmp),
+      below_min_score: placements
+        .iter()
+        .filter(|p| p.score < min_acceptable)
+        .count(),
+    }
+  }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockingPair {
+  pub first_user_id: uuid::Uuid,
+  pub second_user_id: uuid::Uuid,
+  /// Compatibility the two would have as roommates.
+  pub score: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PairingReport {
+  pub pairs: usize,
+  /// Whether the pairing is a stable matching. When none exists a greedy
+  /// approximation is used and `blocking_pairs` lists its instabilities.
+  pub stable: bool,
+  /// Students who would both rather room with each other than with their
+  /// assigned partners.
+  pub blocking_pairs: Vec<BlockingPair>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AllocationPlan {
+  pub mode: String,
+  pub matching_settings_version: i32,
+  pub dry_run: bool,
+  /// Set once the plan has been committed as applications.
+  pub run_id: Option<uuid::Uuid>,
+  pub placements: Vec<PlannedPlacement>,
+  pub unplaced: Vec<UnplacedStudent>,
+  pub metrics: PlanMetrics,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub pairing: Option<PairingReport>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub priorities: Option<Vec<PriorityRule>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub lottery: Option<LotteryResult>,
+}
+
+/// One level of the dorm's priority order over students. Earlier rules
+/// dominate later ones; remaining ties go to the earlier registration.
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum PriorityRule {
+  /// Students with a social-benefit category first.
+  SocialBenefit,
+  /// Higher (or, with `senior_first: false`, lower) courses first.
+  Course { senior_first: bool },
+  /// Students of the listed faculties first, in the listed order.
+  Faculty { faculties: Vec<String> },
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeferredAcceptanceRequest {
+  /// Only compute the plan without creating applications. Defaults to true.
+  pub dry_run: Option<bool>,
+  pub priorities: Vec<PriorityRule>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RoomPreferencesRequest {
+  /// Rooms from `/rooms/search`, most wanted first.
+  pub room_ids: Vec<uuid::Uuid>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SocialBenefitRequest {
+  pub category: String,
+}
+
+/// One room's draw: the eligible students who ranked the room, in the order
+/// they were drawn, and those of them the room admitted.
+#[derive(Serialize, ToSchema)]
+pub struct LotteryDraw {
+  pub room_id: uuid::Uuid,
+  pub room_number: String,
+  pub drawn: Vec<uuid::Uuid>,
+  pub admitted: Vec<uuid::Uuid>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LotteryResult {
+  #[serde(flatten)]
+  pub lottery: Lottery,
+  pub draws: Vec<LotteryDraw>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ApplicationStatusRequest {
+  pub status: ApplicationStatus,
+  pub comment: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RoomStatusRequest {
+  pub status: RoomStatus,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CurrentResidency {
+  #[serde(flatten)]
+  pub residency: Residency,
+  pub room: Room,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RoomOverview {
+  #[serde(flatten)]
+  pub room: Room,
+  pub residents: Vec<StudentProfile>,
+  /// `pair_scores[i][j]` is the match between residents `i` and `j`; the
+  /// diagonal is `null`.
+  pub pair_scores: Vec<Vec<Option<f64>>>,
+  /// Mean and minimum over all resident pairs; `null` with fewer than two
+  /// residents.
+  pub mean_pair_score: Option<f64>,
+  pub min_pair_score: Option<f64>,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OverviewSort {
+  Number,
+  MeanPairScore,
+  MinPairScore,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+  Asc,
+  Desc,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ApplicationQuery {
+  /// Only applications for rooms in this building.
+  pub building: Option<String>,
+  /// Only applications in this status.
+  #[param(inline)]
+  pub status: Option<ApplicationStatus>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct RoomOverviewQuery {
+  /// Keep rooms whose mean pair score is at least this.
+  pub min_mean_score: Option<f64>,
+  /// Keep rooms whose mean pair score is at most this.
+  pub max_mean_score: Option<f64>,
+  /// Keep rooms whose worst pair scores at least this.
+  pub min_min_score: Option<f64>,
+  /// Keep rooms whose worst pair scores at most this.
+  pub max_min_score: Option<f64>,
+  /// Defaults to `number`. Rooms without a score sort last.
+  #[param(inline)]
+  pub sort_by: Option<OverviewSort>,
+  /// Defaults to `asc`, i.e. the worst matched rooms first.
+  #[param(inline)]
+  pub order: Option<SortOrder>,
+}
@@ -1,23 +1,127 @@
-use crate::models::RoomStats;
-use dormmatch_common::models::room::Room;
-use utoipa::OpenApi;
+use crate::models::{
+  AllocationPlan, AllocationRequest, ApplicationStatusRequest, BlockingPair, CriterionScore,
+  CurrentResidency, DeferredAcceptanceRequest, LotteryDraw, LotteryResult, MatchBreakdown,
+  OverviewSort, PairingReport, PlanMetrics, PlannedPlacement, PriorityRule, RoomMatch,
+  RoomOverview, RoomPreferencesRequest, RoomStats, RoomStatusRequest, ScoredRoom,
+  SocialBenefitRequest, SortOrder, UnplacedStudent,
+};
+use crate::services::matching::Criterion;
+use crate::controllers::settings::MatchingSettingsRequest;
+use dormmatch_common::models::{
+  allocation_run::AllocationRun,
+  application::{Application, ApplicationStatus},
+  lottery::Lottery,
+  matching_settings::MatchingSettings,
+  preference::{RoomPreference, SocialBenefit},
+  residency::Residency,
+  room::{Room, RoomStatus},
+};
+use utoipa::{
+  openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+  Modify, OpenApi,
+};
 
 #[derive(OpenApi)]
 #[openapi(
   paths(
     crate::controllers::rooms::create_room,
     crate::controllers::rooms::search_rooms,
+    crate::controllers::rooms::get_rooms_overview,
+    crate::controllers::rooms::get_room_match,
+    crate::controllers::rooms::get_student_room_match,
+    crate::controllers::rooms::set_room_status,
+    crate::controllers::residencies::get_room_residents,
+    crate::controllers::residencies::get_current_residency,
+    crate::controllers::residencies::move_out,
+    crate::controllers::preferences::set_preferences,
+    crate::controllers::preferences::get_preferences,
     crate::controllers::rooms::apply_room,
     crate::controllers::rooms::get_applications,
+    crate::controllers::rooms::list_applications,
     crate::controllers::rooms::approve_application,
     crate::controllers::rooms::reject_application,
+    crate::controllers::rooms::set_application_status,
     crate::controllers::rooms::get_stats,
-    crate::controllers::rooms::auto_assign
+    crate::controllers::rooms::auto_assign,
+    crate::controllers::settings::list_settings,
+    crate::controllers::settings::get_active_settings,
+    crate::controllers::settings::get_settings,
+    crate::controllers::settings::create_settings,
+    crate::controllers::settings::update_settings,
+    crate::controllers::settings::delete_settings,
+    crate::controllers::settings::activate_settings,
+    crate::controllers::allocations::run_batch_allocation,
+    crate::controllers::allocations::run_stable_roommates_allocation,
+    crate::controllers::allocations::run_deferred_acceptance,
+    crate::controllers::allocations::set_social_benefit,
+    crate::controllers::allocations::remove_social_benefit,
+    crate::controllers::allocations::announce_lottery,
+    crate::controllers::allocations::list_lotteries,
+    crate::controllers::allocations::get_lottery,
+    crate::controllers::allocations::draw_lottery,
+    crate::controllers::allocations::list_allocation_runs
   ),
-  components(schemas(Room, RoomStats))
+  components(schemas(
+    Room,
+    RoomStatus,
+    RoomStatusRequest,
+    RoomStats,
+    Residency,
+    CurrentResidency,
+    ScoredRoom,
+    RoomOverview,
+    OverviewSort,
+    SortOrder,
+    Criterion,
+    CriterionScore,
+    MatchBreakdown,
+    RoomMatch,
+    MatchingSettings,
+    MatchingSettingsRequest,
+    Application,
+    ApplicationStatus,
+    ApplicationStatusRequest,
+    AllocationRun,
+    AllocationRequest,
+    AllocationPlan,
+    PlannedPlacement,
+    UnplacedStudent,
+    PlanMetrics,
+    PairingReport,
+    BlockingPair,
+    PriorityRule,
+    DeferredAcceptanceRequest,
+    RoomPreference,
+    RoomPreferencesRequest,
+    SocialBenefit,
+    SocialBenefitRequest,
+    Lottery,
+    LotteryDraw,
+    LotteryResult
+  )),
+  modifiers(&SecurityAddon)
 )]
 pub struct ApiDoc;
 
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+  fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+    let components = openapi
+      .components
+      .get_or_insert_with(utoipa::openapi::Components::new);
+    components.add_security_scheme(
+      "bearerAuth",
+      SecurityScheme::Http(
+        HttpBuilder::new()
+          .scheme(HttpAuthScheme::Bearer)
+          .bearer_format("JWT")
+          .build(),
+      ),
+    );
+  }
+}
+
 pub fn configure_openapi(cfg: &mut actix_web::web::ServiceConfig) {
   cfg.service(
     utoipa_swagger_ui::SwaggerUi::new("/swagger-ui/{_:.*}")
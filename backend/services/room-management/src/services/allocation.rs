@@ -0,0 +1,588 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use dormmatch_common::models::{
+  matching_settings::MatchingSettings, profile::StudentProfile, room::Room,
+};
+use uuid::Uuid;
+
+use crate::models::{
+  BlockingPair, LotteryDraw, MatchBreakdown, PairingReport, PlannedPlacement, PriorityRule,
+  UnplacedStudent,
+};
+use crate::services::{
+  assignment::max_weight_assignment,
+  deferred_acceptance::{deferred_acceptance, Refusal},
+  lottery,
+  matching::MatchingService,
+  stable_roommates::{blocking_pairs, greedy_matching, stable_roommates},
+};
+
+/// Upper bound on local-search sweeps after the initial assignment.
+const IMPROVEMENT_PASSES: usize = 10;
+
+/// Minimal gain for a local-search step to count as an improvement.
+const EPSILON: f64 = 1e-9;
+
+/// Everything an allocation run works on.
+pub struct AllocationInput {
+  pub students: Vec<StudentProfile>,
+  pub rooms: Vec<Room>,
+  pub occupants: HashMap<Uuid, Vec<StudentProfile>>,
+  pub settings: MatchingSettings,
+}
+
+impl AllocationInput {
+  pub fn occupants_of(&self, room: &Room) -> &[StudentProfile] {
+    self
+      .occupants
+      .get(&room.id)
+      .map(Vec::as_slice)
+      .unwrap_or_default()
+  }
+
+  pub fn free_slots(room: &Room) -> usize {
+    (room.capacity - room.current_occupants).max(0) as usize
+  }
+
+  /// Score of `student` when sharing `room` with its current occupants and
+  /// the given newcomers.
+  pub fn score_with(&self, student: usize, room: usize, newcomers: &[usize]) -> f64 {
+    self.explain_with(student, room, newcomers).score
+  }
+
+  /// Breakdown behind `score_with`.
+  pub fn explain_with(&self, student: usize, room: usize, newcomers: &[usize]) -> MatchBreakdown {
+    let mut others = self.occupants_of(&self.rooms[room]).to_vec();
+    others.extend(
+      newcomers
+        .iter()
+        .filter(|&&other| other != student)
+        .map(|&other| self.students[other].clone()),
+    );
+    MatchingService::explain(&self.students[student], &others, &self.settings)
+  }
+}
+
+/// Places students so that their total compatibility is as high as possible.
+///
+/// Students are first assigned to free beds with the Hungarian algorithm,
+/// scoring each bed against the room's current occupants. Because students
+/// placed in the same room also affect each other, the result is then
+/// refined by moving and swapping students between rooms while the total
+/// score keeps growing. Hard restrictions (`is_compatible`) are never
+/// violated, and every student reaches the settings' minimum score against
+/// the room's current occupants, if it has any. Students placed into the
+/// same room in this run are not checked against each other, so their final
+/// score can end up below the minimum; `PlanMetrics::below_min_score` counts
+/// those.
+pub fn plan_batch(input: &AllocationInput) -> (Vec<PlannedPlacement>, Vec<UnplacedStudent>) {
+  let students = &input.students;
+  let rooms = &input.rooms;
+
+  let base_scores: Vec<Vec<Option<f64>>> = students
+    .iter()
+    .enumerate()
+    .map(|(s, profile)| {
+      rooms
+        .iter()
+        .enumerate()
+        .map(|(r, room)| {
+          if !MatchingService::is_compatible(room, profile) {
+            return None;
+          }
+          let breakdown = input.explain_with(s, r, &[]);
+          MatchingService::meets_minimum(&breakdown, &input.settings).then_some(breakdown.score)
+        })
+        .collect()
+    })
+    .collect();
+
+  let slots: Vec<usize> = rooms
+    .iter()
+    .enumerate()
+    .flat_map(|(r, room)| std::iter::repeat_n(r, AllocationInput::free_slots(room)))
+    .collect();
+  let weights: Vec<Vec<Option<f64>>> = base_scores
+    .iter()
+    .map(|scores| slots.iter().map(|&r| scores[r]).collect())
+    .collect();
+
+  let mut layout = Layout {
+    input,
+    eligible: &base_scores,
+    room_of: vec![None; students.len()],
+    members: vec![Vec::new(); rooms.len()],
+  };
+  for (s, slot) in max_weight_assignment(&weights).into_iter().enumerate() {
+    if let Some(slot) = slot {
+      layout.place(s, slots[slot]);
+    }
+  }
+  layout.improve();
+
+  let mut placements = Vec::new();
+  let mut unplaced = Vec::new();
+  for (s, profile) in students.iter().enumerate() {
+    match layout.room_of[s] {
+      Some(r) => placements.push(PlannedPlacement {
+        user_id: profile.user_id,
+        room_id: rooms[r].id,
+        room_number: rooms[r].number.clone(),
+        score: input.score_with(s, r, &layout.members[r]),
+        note: None,
+      }),
+      None => unplaced.push(UnplacedStudent {
+        user_id: profile.user_id,
+        reason: if base_scores[s].iter().any(Option::is_some) {
+          "All compatible rooms are full".to_string()
+        } else {
+          "No compatible room reaches the minimum match score".to_string()
+        },
+        room_id: None,
+      }),
+    }
+  }
+  placements.sort_by(|a, b| a.room_number.cmp(&b.room_number));
+
+  (placements, unplaced)
+}
+
+/// Pairs students first and then places each pair into an empty two-person
+/// room that fits both of them.
+///
+/// Two students are acceptable to each other when their mutual score reaches
+/// the settings' minimum and at least one such room admits both. Preferences
+/// follow the score, with ties broken by a fixed pair order so that all
+/// lists are strict. Irving's algorithm yields a stable pairing when one
+/// exists; otherwise pairs are formed greedily by descending score.
+pub fn plan_pairs(
+  input: &AllocationInput,
+) -> (Vec<PlannedPlacement>, Vec<UnplacedStudent>, PairingReport) {
+  let students = &input.students;
+  let rooms: Vec<&Room> = input
+    .rooms
+    .iter()
+    .filter(|room| room.capacity == 2 && room.current_occupants == 0)
+    .collect();
+  let fits = |s: usize, room: &Room| MatchingService::is_compatible(room, &students[s]);
+
+  let n = students.len();
+  let mut scores: Vec<Vec<Option<f64>>> = vec![vec![None; n]; n];
+  let mut acceptable: Vec<(usize, usize)> = Vec::new();
+  for a in 0..n {
+    for b in (a + 1)..n {
+      if !rooms.iter().any(|room| fits(a, room) && fits(b, room)) {
+        continue;
+      }
+      let breakdown = MatchingService::explain(
+        &students[a],
+        std::slice::from_ref(&students[b]),
+        &input.settings,
+      );
+      if MatchingService::meets_minimum(&breakdown, &input.settings) {
+        let score = breakdown.score;
+        scores[a][b] = Some(score);
+        scores[b][a] = Some(score);
+        acceptable.push((a, b));
+      }
+    }
+  }
+  // Global strict order on pairs: higher score first, then by index.
+  let pair_order = |&(a, b): &(usize, usize), &(c, d): &(usize, usize)| {
+    let (x, y) = (scores[a][b].unwrap_or_default(), scores[c][d].unwrap_or_default());
+    y.total_cmp(&x).then((a.min(b), a.max(b)).cmp(&(c.min(d), c.max(d))))
+  };
+  acceptable.sort_by(pair_order);
+
+  let prefs: Vec<Vec<usize>> = (0..n)
+    .map(|a| {
+      let mut list: Vec<usize> = (0..n).filter(|&b| scores[a][b].is_some()).collect();
+      list.sort_by(|&b, &c| pair_order(&(a, b), &(a, c)));
+      list
+    })
+    .collect();
+
+  let (matching, stable) = match stable_roommates(&prefs) {
+    Some(matching) => (matching, true),
+    None => (greedy_matching(n, &acceptable), false),
+  };
+
+  let pairs: Vec<(usize, usize)> = matching
+    .iter()
+    .enumerate()
+    .filter_map(|(a, partner)| partner.filter(|&b| a < b).map(|b| (a, b)))
+    .collect();
+  let weights: Vec<Vec<Option<f64>>> = pairs
+    .iter()
+    .map(|&(a, b)| {
+      rooms
+        .iter()
+        .map(|room| (fits(a, room) && fits(b, room)).then(|| scores[a][b]).flatten())
+        .collect()
+    })
+    .collect();
+
+  let mut room_of: Vec<Option<usize>> = vec![None; n];
+  for (p, room) in max_weight_assignment(&weights).into_iter().enumerate() {
+    if let Some(room) = room {
+      let (a, b) = pairs[p];
+      room_of[a] = Some(room);
+      room_of[b] = Some(room);
+    }
+  }
+
+  let mut placements = Vec::new();
+  let mut unplaced = Vec::new();
+  for (s, profile) in students.iter().enumerate() {
+    match (room_of[s], matching[s]) {
+      (Some(r), Some(partner)) => placements.push(PlannedPlacement {
+        user_id: profile.user_id,
+        room_id: rooms[r].id,
+        room_number: rooms[r].number.clone(),
+        score: scores[s][partner].unwrap_or_default(),
+        note: None,
+      }),
+      (None, Some(_)) => unplaced.push(UnplacedStudent {
+        user_id: profile.user_id,
+        reason: "No free two-person room left for the pair".to_string(),
+        room_id: None,
+      }),
+      _ => unplaced.push(UnplacedStudent {
+        user_id: profile.user_id,
+        reason: if prefs[s].is_empty() {
+          "No acceptable roommate".to_string()
+        } else {
+          "Every acceptable roommate was paired with someone they prefer".to_string()
+        },
+        room_id: None,
+      }),
+    }
+  }
+  placements.sort_by(|a, b| a.room_number.cmp(&b.room_number));
+
+  let report = PairingReport {
+    pairs: pairs.len(),
+    stable,
+    blocking_pairs: blocking_pairs(&prefs, &matching)
+      .into_iter()
+      .map(|(a, b)| BlockingPair {
+        first_user_id: students[a].user_id,
+        second_user_id: students[b].user_id,
+        score: scores[a][b].unwrap_or_default(),
+      })
+      .collect(),
+  };
+
+  (placements, unplaced, report)
+}
+
+/// Admits students to their ranked rooms with student-proposing deferred
+/// acceptance.
+///
+/// Only students who ranked at least one room take part. Every student is
+/// ordered by `rules` the same way in every room; ties go to whoever
+/// registered first. Each placement and each refusal carries a note listing
+/// what happened to the student's higher choices.
+pub fn plan_deferred_acceptance(
+  input: &AllocationInput,
+  preferences: &HashMap<Uuid, Vec<Uuid>>,
+  benefits: &HashSet<Uuid>,
+  rules: &[PriorityRule],
+) -> (Vec<PlannedPlacement>, Vec<UnplacedStudent>) {
+  let priority = |_room: usize, a: usize, b: usize| {
+    let (pa, pb) = (&input.students[a], &input.students[b]);
+    rules
+      .iter()
+      .map(|rule| match rule {
+        PriorityRule::SocialBenefit => {
+          benefits.contains(&pb.user_id).cmp(&benefits.contains(&pa.user_id))
+        }
+        PriorityRule::Course { senior_first: true } => pb.course.cmp(&pa.course),
+        PriorityRule::Course { senior_first: false } => pa.course.cmp(&pb.course),
+        PriorityRule::Faculty { faculties } => {
+          let position = |faculty: &str| {
+            faculties
+              .iter()
+              .position(|f| f == faculty)
+              .unwrap_or(faculties.len())
+          };
+          position(&pa.faculty).cmp(&position(&pb.faculty))
+        }
+      })
+      .fold(Ordering::Equal, Ordering::then)
+      .then(a.cmp(&b))
+  };
+
+  admit_by_preference(input, preferences, "filled by higher-priority students", priority)
+}
+
+/// Admits students to their ranked rooms by lottery.
+///
+/// Every room draws among the eligible students who ranked it, using
+/// `lottery::draw_order` with the lottery's `seed`; the draw order is the
+/// room's priority in deferred acceptance, so a student keeps the best ranked
+/// room they drew high enough for. Returns the draws next to the plan so
+/// anyone can re-run them.
+pub fn plan_lottery(
+  input: &AllocationInput,
+  preferences: &HashMap<Uuid, Vec<Uuid>>,
+  seed: &str,
+) -> (Vec<PlannedPlacement>, Vec<UnplacedStudent>, Vec<LotteryDraw>) {
+  let student_index: HashMap<Uuid, usize> = input
+    .students
+    .iter()
+    .enumerate()
+    .map(|(s, student)| (student.user_id, s))
+    .collect();
+
+  let mut draws: Vec<LotteryDraw> = input
+    .rooms
+    .iter()
+    .map(|room| {
+      let applicants: Vec<Uuid> = input
+        .students
+        .iter()
+        .filter(|student| {
+          preferences
+            .get(&student.user_id)
+            .is_some_and(|ranked| ranked.contains(&room.id))
+            && MatchingService::is_compatible(room, student)
+        })
+        .map(|student| student.user_id)
+        .collect();
+      LotteryDraw {
+        room_id: room.id,
+        room_number: room.number.clone(),
+        drawn: lottery::draw_order(seed, room.id, &applicants),
+        admitted: Vec::new(),
+      }
+    })
+    .collect();
+
+  // Position of every student in each room's draw.
+  let positions: Vec<HashMap<usize, usize>> = draws
+    .iter()
+    .map(|draw| {
+      draw
+        .drawn
+        .iter()
+        .enumerate()
+        .map(|(position, user_id)| (student_index[user_id], position))
+        .collect()
+    })
+    .collect();
+  let priority =
+    |room: usize, a: usize, b: usize| positions[room][&a].cmp(&positions[room][&b]);
+
+  let (placements, unplaced) =
+    admit_by_preference(input, preferences, "filled by students drawn earlier", priority);
+
+  for draw in &mut draws {
+    draw.admitted = draw
+      .drawn
+      .iter()
+      .filter(|user_id| {
+        placements
+          .iter()
+          .any(|p| p.user_id == **user_id && p.room_id == draw.room_id)
+      })
+      .copied()
+      .collect();
+  }
+  draws.retain(|draw| !draw.drawn.is_empty());
+
+  (placements, unplaced, draws)
+}
+
+/// Deferred acceptance over the students' ranked rooms, with each room
+/// ordering the students (indices into `input.students`) by `priority`.
+/// `outranked` explains a refusal by a full room.
+fn admit_by_preference(
+  input: &AllocationInput,
+  preferences: &HashMap<Uuid, Vec<Uuid>>,
+  outranked: &str,
+  priority: impl Fn(usize, usize, usize) -> Ordering,
+) -> (Vec<PlannedPlacement>, Vec<UnplacedStudent>) {
+  let rooms = &input.rooms;
+  let participants: Vec<usize> = (0..input.students.len())
+    .filter(|&s| preferences.contains_key(&input.students[s].user_id))
+    .collect();
+
+  // Ranked rooms that are no longer available map to a sentinel room
+  // without beds.
+  let unavailable = rooms.len();
+  let room_index: HashMap<Uuid, usize> = rooms.iter().enumerate().map(|(r, room)| (room.id, r)).collect();
+  let ranked: Vec<&Vec<Uuid>> = participants
+    .iter()
+    .map(|&s| &preferences[&input.students[s].user_id])
+    .collect();
+  let prefs: Vec<Vec<usize>> = ranked
+    .iter()
+    .map(|ids| ids.iter().map(|id| *room_index.get(id).unwrap_or(&unavailable)).collect())
+    .collect();
+  let capacities: Vec<usize> = rooms
+    .iter()
+    .map(AllocationInput::free_slots)
+    .chain(std::iter::once(0))
+    .collect();
+
+  let profile = |p: usize| &input.students[participants[p]];
+  let eligible =
+    |p: usize, r: usize| r != unavailable && MatchingService::is_compatible(&rooms[r], profile(p));
+
+  let outcome = deferred_acceptance(&prefs, &capacities, eligible, |r, a, b| {
+    priority(r, participants[a], participants[b])
+  });
+
+  let mut members: Vec<Vec<usize>> = vec![Vec::new(); rooms.len()];
+  for (p, admitted) in outcome.admitted.iter().enumerate() {
+    if let Some(choice) = admitted {
+      members[prefs[p][*choice]].push(participants[p]);
+    }
+  }
+
+  let describe = |p: usize| -> Vec<String> {
+    outcome.refusals[p]
+      .iter()
+      .enumerate()
+      .map(|(choice, refusal)| {
+        let r = prefs[p][choice];
+        let room = if r == unavailable {
+          "a room that is no longer available".to_string()
+        } else {
+          format!("room {}", rooms[r].number)
+        };
+        let why = match refusal {
+          _ if r == unavailable => "not available",
+          Refusal::Ineligible => "restrictions not met",
+          Refusal::Outranked => outranked,
+        };
+        format!("#{} {} ({})", choice + 1, room, why)
+      })
+      .collect()
+  };
+
+  let mut placements = Vec::new();
+  let mut unplaced = Vec::new();
+  for (p, admitted) in outcome.admitted.iter().enumerate() {
+    let s = participants[p];
+    let refused = describe(p);
+    match admitted {
+      Some(choice) => {
+        let r = prefs[p][*choice];
+        let mut note = format!("admitted to choice #{}", choice + 1);
+        if !refused.is_empty() {
+          note.push_str(&format!("; higher choices: {}", refused.join(", ")));
+        }
+        placements.push(PlannedPlacement {
+          user_id: input.students[s].user_id,
+          room_id: rooms[r].id,
+          room_number: rooms[r].number.clone(),
+          score: input.score_with(s, r, &members[r]),
+          note: Some(note),
+        });
+      }
+      None => unplaced.push(UnplacedStudent {
+        user_id: input.students[s].user_id,
+        reason: format!("Not admitted to any ranked room: {}", refused.join(", ")),
+        room_id: ranked[p].first().copied(),
+      }),
+    }
+  }
+  placements.sort_by(|a, b| a.room_number.cmp(&b.room_number));
+
+  (placements, unplaced)
+}
+
+/// Which room every student currently sits in during local search.
+struct Layout<'a> {
+  input: &'a AllocationInput,
+  eligible: &'a [Vec<Option<f64>>],
+  room_of: Vec<Option<usize>>,
+  members: Vec<Vec<usize>>,
+}
+
+impl Layout<'_> {
+  fn place(&mut self, student: usize, room: usize) {
+    self.room_of[student] = Some(room);
+    self.members[room].push(student);
+  }
+
+  fn can_host(&self, student: usize, room: usize) -> bool {
+    self.eligible[student][room].is_some()
+  }
+
+  fn has_free_bed(&self, room: usize) -> bool {
+    self.members[room].len() < AllocationInput::free_slots(&self.input.rooms[room])
+  }
+
+  fn room_total(&self, room: usize, members: &[usize]) -> f64 {
+    members
+      .iter()
+      .map(|&s| self.input.score_with(s, room, members))
+      .sum()
+  }
+
+  /// Members of `room` after removing `leaving` and adding `joining`.
+  fn with(&self, room: usize, leaving: Option<usize>, joining: Option<usize>) -> Vec<usize> {
+    self.members[room]
+      .iter()
+      .copied()
+      .filter(|&s| Some(s) != leaving)
+      .chain(joining)
+      .collect()
+  }
+
+  fn improve(&mut self) {
+    let students = self.input.students.len();
+    for _ in 0..IMPROVEMENT_PASSES {
+      let mut improved = false;
+
+      for i in 0..students {
+        for target in 0..self.input.rooms.len() {
+          let Some(from) = self.room_of[i] else { break };
+          if target == from || !self.has_free_bed(target) || !self.can_host(i, target) {
+            continue;
+          }
+          let new_from = self.with(from, Some(i), None);
+          let new_target = self.with(target, None, Some(i));
+          let before = self.room_total(from, &self.members[from])
+            + self.room_total(target, &self.members[target]);
+          let after = self.room_total(from, &new_from) + self.room_total(target, &new_target);
+          if after > before + EPSILON {
+            self.members[from] = new_from;
+            self.members[target] = new_target;
+            self.room_of[i] = Some(target);
+            improved = true;
+          }
+        }
+
+        for j in (i + 1)..students {
+          let (Some(a), Some(b)) = (self.room_of[i], self.room_of[j]) else {
+            continue;
+          };
+          if a == b || !self.can_host(i, b) || !self.can_host(j, a) {
+            continue;
+          }
+          let new_a = self.with(a, Some(i), Some(j));
+          let new_b = self.with(b, Some(j), Some(i));
+          let before =
+            self.room_total(a, &self.members[a]) + self.room_total(b, &self.members[b]);
+          let after = self.room_total(a, &new_a) + self.room_total(b, &new_b);
+          if after > before + EPSILON {
+            self.members[a] = new_a;
+            self.members[b] = new_b;
+            self.room_of[i] = Some(b);
+            self.room_of[j] = Some(a);
+            improved = true;
+          }
+        }
+      }
+
+      if !improved {
+        break;
+      }
+    }
+  }
+}
@@ -0,0 +1,169 @@
+/// Maximum-weight bipartite assignment (Hungarian algorithm).
+///
+/// `weights[row][col]` is the gain of assigning `row` to `col`, or `None`
+/// when the pair is not allowed. Every row gets at most one column and every
+/// column at most one row. The number of assigned pairs is maximised first
+/// and the total weight second. Returns the chosen column for each row.
+pub fn max_weight_assignment(weights: &[Vec<Option<f64>>]) -> Vec<Option<usize>> {
+  let rows = weights.len();
+  let cols = weights.first().map_or(0, Vec::len);
+  if rows == 0 || cols == 0 {
+    return vec![None; rows];
+  }
+
+  // Makes a single extra assignment worth more than any achievable total
+  // weight gain, so cardinality always wins.
+  let max_weight = weights
+    .iter()
+    .flatten()
+    .flatten()
+    .fold(0.0_f64, |acc, w| acc.max(w.abs()));
+  let bonus = (max_weight + 1.0) * (rows.max(cols) + 1) as f64;
+  let cost = |row: usize, col: usize| match weights[row][col] {
+    Some(weight) => -(bonus + weight),
+    None => 0.0,
+  };
+
+  let assignment = if rows <= cols {
+    hungarian(rows, cols, cost)
+  } else {
+    let transposed = hungarian(cols, rows, |col, row| cost(row, col));
+    let mut assignment = vec![None; rows];
+    for (col, row) in transposed.into_iter().enumerate() {
+      if let Some(row) = row {
+        assignment[row] = Some(col);
+      }
+    }
+    assignment
+  };
+
+  assignment
+    .into_iter()
+    .enumerate()
+    .map(|(row, col)| col.filter(|&col| weights[row][col].is_some()))
+    .collect()
+}
+
+/// Minimum-cost assignment of `n` rows into `m >= n` columns; every row is
+/// assigned. Classic O(n²·m) potentials formulation.
+fn hungarian(n: usize, m: usize, cost: impl Fn(usize, usize) -> f64) -> Vec<Option<usize>> {
+  let mut u = vec![0.0; n + 1];
+  let mut v = vec![0.0; m + 1];
+  // p[j]: row (1-based) matched to column j; way[j]: previous column on the
+  // augmenting path.
+  let mut p = vec![0usize; m + 1];
+  let mut way = vec![0usize; m + 1];
+
+  for i in 1..=n {
+    p[0] = i;
+    let mut j0 = 0;
+    let mut minv = vec![f64::INFINITY; m + 1];
+    let mut used = vec![false; m + 1];
+    loop {
+      used[j0] = true;
+      let i0 = p[j0];
+      let mut delta = f64::INFINITY;
+      let mut j1 = 0;
+      for j in 1..=m {
+        if used[j] {
+          continue;
+        }
+        let cur = cost(i0 - 1, j - 1) - u[i0] - v[j];
+        if cur < minv[j] {
+          minv[j] = cur;
+          way[j] = j0;
+        }
+        if minv[j] < delta {
+          delta = minv[j];
+          j1 = j;
+        }
+      }
+      for j in 0..=m {
+        if used[j] {
+          u[p[j]] += delta;
+          v[j] -= delta;
+        } else {
+          minv[j] -= delta;
+        }
+      }
+      j0 = j1;
+      if p[j0] == 0 {
+        break;
+      }
+    }
+    loop {
+      let j1 = way[j0];
+      p[j0] = p[j1];
+      j0 = j1;
+      if j0 == 0 {
+        break;
+      }
+    }
+  }
+
+  let mut assignment = vec![None; n];
+  for j in 1..=m {
+    if p[j] != 0 {
+      assignment[p[j] - 1] = Some(j - 1);
+    }
+  }
+  assignment
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn maximises_total_weight() {
+    let weights = vec![
+      vec![Some(1.0), Some(2.0), Some(3.0)],
+      vec![Some(2.0), Some(4.0), Some(6.0)],
+      vec![Some(3.0), Some(6.0), Some(9.0)],
+    ];
+    assert_eq!(max_weight_assignment(&weights), vec![Some(0), Some(1), Some(2)]);
+  }
+
+  #[test]
+  fn more_columns_than_rows() {
+    let weights = vec![
+      vec![Some(1.0), Some(5.0), Some(2.0), Some(0.0)],
+      vec![Some(4.0), Some(5.0), Some(1.0), Some(0.0)],
+    ];
+    assert_eq!(max_weight_assignment(&weights), vec![Some(1), Some(0)]);
+  }
+
+  #[test]
+  fn more_rows_than_columns() {
+    let weights = vec![
+      vec![Some(1.0), Some(2.0)],
+      vec![Some(5.0), Some(1.0)],
+      vec![Some(3.0), Some(6.0)],
+    ];
+    assert_eq!(max_weight_assignment(&weights), vec![None, Some(0), Some(1)]);
+  }
+
+  #[test]
+  fn never_uses_infeasible_cells() {
+    let weights = vec![
+      vec![None, Some(1.0)],
+      vec![None, Some(7.0)],
+      vec![None, None],
+    ];
+    assert_eq!(max_weight_assignment(&weights), vec![None, Some(1), None]);
+  }
+
+  #[test]
+  fn prefers_more_placements_over_higher_total() {
+    // Row 0 alone in column 0 would be worth 100, but then row 1 gets
+    // nothing.
+    let weights = vec![vec![Some(100.0), Some(1.0)], vec![Some(1.0), None]];
+    assert_eq!(max_weight_assignment(&weights), vec![Some(1), Some(0)]);
+  }
+
+  #[test]
+  fn empty_input() {
+    assert_eq!(max_weight_assignment(&[]), Vec::<Option<usize>>::new());
+    assert_eq!(max_weight_assignment(&[vec![], vec![]]), vec![None, None]);
+  }
+}
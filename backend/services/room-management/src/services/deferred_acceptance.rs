@@ -0,0 +1,169 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// Why a student did not end up in one of their ranked rooms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+  /// The room's restrictions do not admit the student.
+  Ineligible,
+  /// Every bed went to students with higher priority.
+  Outranked,
+}
+
+pub struct DeferredAcceptance {
+  /// Index into `prefs[student]` of the room each student was admitted to.
+  pub admitted: Vec<Option<usize>>,
+  /// For every student, the refusal behind each choice ranked above the
+  /// final one (or behind all choices when nothing was admitted).
+  pub refusals: Vec<Vec<Refusal>>,
+}
+
+/// Student-proposing deferred acceptance with room capacities.
+///
+/// Students propose to rooms in the order of `prefs`; each room tentatively
+/// keeps the best `capacities[room]` proposers according to
+/// `priority(room, a, b)` (`Ordering::Less` meaning `a` is preferred) and rejects
+/// the rest, who move on to their next choice. The outcome is stable and
+/// no student can gain a better room by misreporting their ranking.
+pub fn deferred_acceptance(
+  prefs: &[Vec<usize>],
+  capacities: &[usize],
+  eligible: impl Fn(usize, usize) -> bool,
+  priority: impl Fn(usize, usize, usize) -> Ordering,
+) -> DeferredAcceptance {
+  let students = prefs.len();
+  let mut next = vec![0usize; students];
+  let mut refusals: Vec<Vec<Refusal>> = vec![Vec::new(); students];
+  let mut held: Vec<Vec<usize>> = vec![Vec::new(); capacities.len()];
+  let mut queue: VecDeque<usize> = (0..students).collect();
+
+  while let Some(student) = queue.pop_front() {
+    let Some(&room) = prefs[student].get(next[student]) else {
+      continue;
+    };
+    next[student] += 1;
+
+    if !eligible(student, room) {
+      refusals[student].push(Refusal::Ineligible);
+      queue.push_back(student);
+      continue;
+    }
+
+    held[room].push(student);
+    held[room].sort_by(|&a, &b| priority(room, a, b));
+    if held[room].len() > capacities[room] {
+      let rejected = held[room].pop().unwrap();
+      refusals[rejected].push(Refusal::Outranked);
+      queue.push_back(rejected);
+    }
+  }
+
+  let mut admitted = vec![None; students];
+  for members in &held {
+    for &student in members {
+      admitted[student] = Some(next[student] - 1);
+    }
+  }
+
+  DeferredAcceptance { admitted, refusals }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn by_index(_room: usize, a: usize, b: usize) -> Ordering {
+    a.cmp(&b)
+  }
+
+  #[test]
+  fn fills_rooms_up_to_capacity() {
+    let prefs = vec![vec![0, 1]; 5];
+    let outcome = deferred_acceptance(&prefs, &[2, 2], |_, _| true, by_index);
+    assert_eq!(
+      outcome.admitted,
+      vec![Some(0), Some(0), Some(1), Some(1), None]
+    );
+    assert_eq!(outcome.refusals[2], vec![Refusal::Outranked]);
+    assert_eq!(
+      outcome.refusals[4],
+      vec![Refusal::Outranked, Refusal::Outranked]
+    );
+  }
+
+  #[test]
+  fn tells_ineligible_from_outranked() {
+    let prefs = vec![vec![0, 1], vec![0], vec![0, 1]];
+    let eligible = |student: usize, room: usize| !(student == 0 && room == 0);
+    let outcome = deferred_acceptance(&prefs, &[1, 1], eligible, by_index);
+    assert_eq!(outcome.admitted, vec![Some(1), Some(0), None]);
+    assert_eq!(outcome.refusals[0], vec![Refusal::Ineligible]);
+    assert!(outcome.refusals[1].is_empty());
+    assert_eq!(
+      outcome.refusals[2],
+      vec![Refusal::Outranked, Refusal::Outranked]
+    );
+  }
+
+  #[test]
+  fn result_has_no_blocking_pair() {
+    // Small deterministic generator so the instance is fixed.
+    let mut state = 0x2545_f491_u64;
+    let mut next = move |bound: usize| {
+      state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+      ((state >> 33) as usize) % bound
+    };
+
+    let (students, rooms) = (14, 5);
+    let capacities: Vec<usize> = (0..rooms).map(|_| 1 + next(3)).collect();
+    let prefs: Vec<Vec<usize>> = (0..students)
+      .map(|_| {
+        let mut list: Vec<usize> = (0..rooms).collect();
+        for i in (1..rooms).rev() {
+          list.swap(i, next(i + 1));
+        }
+        list.truncate(1 + next(rooms));
+        list
+      })
+      .collect();
+    let rank: Vec<Vec<usize>> = (0..rooms)
+      .map(|_| (0..students).map(|_| next(1000)).collect())
+      .collect();
+    let eligible = |student: usize, room: usize| !(student + room).is_multiple_of(4);
+    let priority =
+      |room: usize, a: usize, b: usize| rank[room][a].cmp(&rank[room][b]).then(a.cmp(&b));
+
+    let outcome = deferred_acceptance(&prefs, &capacities, eligible, priority);
+
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); rooms];
+    for (student, admitted) in outcome.admitted.iter().enumerate() {
+      if let Some(choice) = admitted {
+        members[prefs[student][*choice]].push(student);
+      }
+    }
+    for (room, held) in members.iter().enumerate() {
+      assert!(held.len() <= capacities[room]);
+    }
+
+    for (student, ranked) in prefs.iter().enumerate() {
+      let better = outcome.admitted[student].unwrap_or(ranked.len());
+      assert_eq!(outcome.refusals[student].len(), better);
+      for (choice, &room) in ranked[..better].iter().enumerate() {
+        // A room the student prefers must either not admit them or be full
+        // of students it prefers.
+        let blocked = !eligible(student, room)
+          || (members[room].len() == capacities[room]
+            && members[room]
+              .iter()
+              .all(|&other| priority(room, other, student) == Ordering::Less));
+        assert!(blocked, "student {} and room {} block", student, room);
+        let expected = if eligible(student, room) {
+          Refusal::Outranked
+        } else {
+          Refusal::Ineligible
+        };
+        assert_eq!(outcome.refusals[student][choice], expected);
+      }
+    }
+  }
+}
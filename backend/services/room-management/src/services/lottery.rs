@@ -0,0 +1,127 @@
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Fresh lottery seed: 32 random bytes, hex-encoded.
+pub fn generate_seed() -> String {
+  let mut bytes = [0u8; 32];
+  OsRng.fill_bytes(&mut bytes);
+  hex::encode(bytes)
+}
+
+/// Commitment to `seed` published when the lottery is announced: the
+/// hex-encoded SHA-256 of the seed's UTF-8 bytes.
+pub fn seed_hash(seed: &str) -> String {
+  hex::encode(Sha256::digest(seed))
+}
+
+/// Deterministic random numbers for one room's draw.
+///
+/// The `k`-th number (counting from 0) is the first 8 bytes, read as a
+/// big-endian integer, of `SHA-256("{seed}:{room_id}:{k}")`, where `room_id`
+/// is the hyphenated lowercase UUID.
+struct DrawStream<'a> {
+  seed: &'a str,
+  room_id: Uuid,
+  counter: u64,
+}
+
+impl DrawStream<'_> {
+  fn next_u64(&mut self) -> u64 {
+    let digest = Sha256::digest(format!("{}:{}:{}", self.seed, self.room_id, self.counter));
+    self.counter += 1;
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+  }
+
+  /// Uniform integer in `0..bound`; numbers from the biased tail of the
+  /// `u64` range are skipped.
+  fn below(&mut self, bound: u64) -> u64 {
+    let zone = u64::MAX - u64::MAX % bound;
+    loop {
+      let value = self.next_u64();
+      if value < zone {
+        return value % bound;
+      }
+    }
+  }
+}
+
+/// Order in which `applicants` are drawn for `room_id`.
+///
+/// The applicants are sorted by their hyphenated lowercase UUID and shuffled
+/// with Fisher-Yates: for `i` from `n - 1` down to `1`, swap position `i`
+/// with position `below(i + 1)` of the room's draw stream. The result only
+/// depends on the seed, the room and the set of applicants.
+pub fn draw_order(seed: &str, room_id: Uuid, applicants: &[Uuid]) -> Vec<Uuid> {
+  let mut order = applicants.to_vec();
+  order.sort_by_key(|id| id.to_string());
+
+  let mut stream = DrawStream {
+    seed,
+    room_id,
+    counter: 0,
+  };
+  for i in (1..order.len()).rev() {
+    let j = stream.below(i as u64 + 1) as usize;
+    order.swap(i, j);
+  }
+  order
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn applicants() -> Vec<Uuid> {
+    [0x1000, 0x2111, 0x3222, 0x4333, 0x5444, 0x6555]
+      .into_iter()
+      .map(Uuid::from_u128)
+      .collect()
+  }
+
+  /// Pins the published algorithm: anyone re-running it from the
+  /// description above must get exactly this order.
+  #[test]
+  fn fixed_seed_gives_fixed_order() {
+    let order = draw_order("published-seed", Uuid::from_u128(0x42), &applicants());
+    let expected: Vec<Uuid> = [0x2111, 0x1000, 0x3222, 0x5444, 0x4333, 0x6555]
+      .into_iter()
+      .map(Uuid::from_u128)
+      .collect();
+    assert_eq!(order, expected);
+  }
+
+  #[test]
+  fn order_does_not_depend_on_input_order() {
+    let room_id = Uuid::from_u128(0x42);
+    let forward = draw_order("published-seed", room_id, &applicants());
+    let mut reversed = applicants();
+    reversed.reverse();
+    assert_eq!(draw_order("published-seed", room_id, &reversed), forward);
+    let mut rotated = applicants();
+    rotated.rotate_left(2);
+    assert_eq!(draw_order("published-seed", room_id, &rotated), forward);
+  }
+
+  #[test]
+  fn seed_and_room_change_the_draw() {
+    let room_id = Uuid::from_u128(0x42);
+    let order = draw_order("published-seed", room_id, &applicants());
+    assert_ne!(draw_order("another-seed", room_id, &applicants()), order);
+    assert_ne!(
+      draw_order("published-seed", Uuid::from_u128(0x43), &applicants()),
+      order
+    );
+  }
+
+  #[test]
+  fn seed_hash_is_plain_sha256() {
+    assert_eq!(
+      seed_hash("abc"),
+      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    let seed = generate_seed();
+    assert_eq!(seed_hash(&seed).len(), 64);
+    assert_ne!(seed_hash(&seed), seed);
+  }
+}
@@ -1,10 +1,157 @@
-use dormmatch_common::models::{profile::StudentProfile, room::Room};
+use std::collections::{HashMap, HashSet};
+
+use dormmatch_common::models::{
+  matching_settings::MatchingSettings,
+  profile::StudentProfile,
+  room::{Room, RoomStatus},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::{CriterionScore, MatchBreakdown};
+
+/// Score given to a room nobody lives in yet: there is no one to be
+/// (in)compatible with, so it ranks below good matches and above bad ones.
+pub const EMPTY_ROOM_SCORE: f64 = 50.0;
+
+/// Age difference (in years) at which the age criterion drops to zero.
+const AGE_GAP_TOLERANCE: f64 = 10.0;
+
+/// Course difference at which the course criterion drops to zero.
+const COURSE_GAP_TOLERANCE: f64 = 4.0;
+
+/// MBTI types in the same order as `MbtiType` and the rows of `MBTI_TABLE`.
+const MBTI_TYPES: [&str; 16] = [
+  "intj", "intp", "entj", "entp", "infj", "infp", "enfj", "enfp", "istj", "isfj", "estj", "esfj",
+  "istp", "isfp", "estp", "esfp",
+];
+
+/// Pairwise MBTI compatibility from 0 (poor) to 4 (ideal pair), following the
+/// widely used type compatibility chart.
+#[rustfmt::skip]
+const MBTI_TABLE: [[u8; 16]; 16] = [
+  [3, 3, 3, 4, 3, 3, 3, 4, 1, 1, 1, 1, 2, 2, 2, 2], // intj
+  [3, 3, 4, 3, 3, 3, 3, 3, 1, 1, 4, 1, 2, 2, 2, 2], // intp
+  [3, 4, 3, 3, 3, 4, 3, 3, 1, 1, 1, 1, 2, 2, 2, 2], // entj
+  [4, 3, 3, 3, 4, 3, 3, 3, 1, 1, 1, 1, 2, 2, 2, 2], // entp
+  [3, 3, 3, 4, 3, 3, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0], // infj
+  [3, 3, 4, 3, 3, 3, 4, 3, 0, 0, 0, 0, 0, 0, 0, 0], // infp
+  [3, 3, 3, 3, 3, 4, 3, 3, 0, 0, 0, 0, 0, 4, 0, 0], // enfj
+  [4, 3, 3, 3, 4, 3, 3, 3, 0, 0, 0, 0, 0, 0, 0, 0], // enfp
+  [1, 1, 1, 1, 0, 0, 0, 0, 3, 3, 3, 3, 1, 1, 4, 4], // istj
+  [1, 1, 1, 1, 0, 0, 0, 0, 3, 3, 3, 3, 1, 1, 4, 4], // isfj
+  [1, 4, 1, 1, 0, 0, 0, 0, 3, 3, 3, 3, 4, 4, 1, 1], // estj
+  [1, 1, 1, 1, 0, 0, 0, 0, 3, 3, 3, 3, 4, 4, 1, 1], // esfj
+  [2, 2, 2, 2, 0, 0, 0, 0, 1, 1, 4, 4, 1, 1, 1, 1], // istp
+  [2, 2, 2, 2, 0, 0, 4, 0, 1, 1, 4, 4, 1, 1, 1, 1], // isfp
+  [2, 2, 2, 2, 0, 0, 0, 0, 4, 4, 1, 1, 1, 1, 1, 1], // estp
+  [2, 2, 2, 2, 0, 0, 0, 0, 4, 4, 1, 1, 1, 1, 1, 1], // esfp
+];
+
+/// Soft criteria used to rank rooms that already pass `is_compatible`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Criterion {
+  WakeHours,
+  Mbti,
+  Hobbies,
+  Age,
+  Faculty,
+  Course,
+}
+
+impl Criterion {
+  pub const ALL: [Criterion; 6] = [
+    Criterion::WakeHours,
+    Criterion::Mbti,
+    Criterion::Hobbies,
+    Criterion::Age,
+    Criterion::Faculty,
+    Criterion::Course,
+  ];
+
+  pub fn weight(self, settings: &MatchingSettings) -> f64 {
+    match self {
+      Criterion::WakeHours => settings.wake_hours_weight,
+      Criterion::Mbti => settings.mbti_weight,
+      Criterion::Hobbies => settings.hobbies_weight,
+      Criterion::Age => settings.age_weight,
+      Criterion::Faculty => settings.faculty_weight,
+      Criterion::Course => settings.course_weight,
+    }
+  }
+
+  /// Similarity in `[0, 1]` between two students, or `None` when the
+  /// criterion cannot be evaluated for this pair (e.g. a missing MBTI type).
+  pub fn similarity(self, a: &StudentProfile, b: &StudentProfile) -> Option<f64> {
+    match self {
+      Criterion::WakeHours => Some(wake_similarity(&a.wake_hours, &b.wake_hours)),
+      Criterion::Mbti => mbti_similarity(a.mbti.as_deref()?, b.mbti.as_deref()?),
+      Criterion::Hobbies => hobby_similarity(&a.hobbies, &b.hobbies),
+      Criterion::Age => {
+        let gap = (a.age - b.age).abs() as f64;
+        Some((1.0 - gap / AGE_GAP_TOLERANCE).max(0.0))
+      }
+      Criterion::Faculty => Some(if a.faculty == b.faculty { 1.0 } else { 0.0 }),
+      Criterion::Course => {
+        let gap = (a.course - b.course).abs() as f64;
+        Some((1.0 - gap / COURSE_GAP_TOLERANCE).max(0.0))
+      }
+    }
+  }
+}
+
+fn wake_similarity(a: &str, b: &str) -> f64 {
+  if a == b {
+    1.0
+  } else if a == "flexible" || b == "flexible" {
+    0.5
+  } else {
+    0.0
+  }
+}
+
+fn mbti_similarity(a: &str, b: &str) -> Option<f64> {
+  let index = |t: &str| MBTI_TYPES.iter().position(|m| m.eq_ignore_ascii_case(t));
+  Some(MBTI_TABLE[index(a)?][index(b)?] as f64 / 4.0)
+}
+
+fn hobby_set(hobbies: &serde_json::Value) -> HashSet<String> {
+  hobbies
+    .as_array()
+    .map(|items| {
+      items
+        .iter()
+        .filter_map(|h| h.as_str())
+        .map(|h| h.trim().to_lowercase())
+        .filter(|h| !h.is_empty())
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Jaccard overlap of the two hobby lists.
+fn hobby_similarity(a: &serde_json::Value, b: &serde_json::Value) -> Option<f64> {
+  let (a, b) = (hobby_set(a), hobby_set(b));
+  let union = a.union(&b).count();
+  if union == 0 {
+    return None;
+  }
+  Some(a.intersection(&b).count() as f64 / union as f64)
+}
+
+#[derive(Clone)]
+pub struct RankedRoom<'a> {
+  pub room: &'a Room,
+  pub score: f64,
+}
 
 pub struct MatchingService;
 
 impl MatchingService {
   pub fn is_compatible(room: &Room, profile: &StudentProfile) -> bool {
-    if room.status != "available" {
+    if room.status != RoomStatus::Available {
       return false;
     }
 
@@ -31,7 +178,405 @@ impl MatchingService {
     true
   }
 
-  pub fn find_best_room<'a>(profile: &StudentProfile, rooms: &'a [Room]) -> Option<&'a Room> {
-    rooms.iter().find(|room| Self::is_compatible(room, profile))
+  /// Compatibility of `profile` with the given occupants as a percentage.
+  pub fn score(
+    profile: &StudentProfile,
+    occupants: &[StudentProfile],
+    settings: &MatchingSettings,
+  ) -> f64 {
+    Self::explain(profile, occupants, settings).score
+  }
+
+  /// Per-criterion breakdown behind `score`.
+  ///
+  /// Each criterion's similarity is averaged over the occupants it can be
+  /// evaluated for; criteria that apply to nobody are left out and the
+  /// remaining weights are renormalised, so the contributions add up to the
+  /// final score.
+  pub fn explain(
+    profile: &StudentProfile,
+    occupants: &[StudentProfile],
+    settings: &MatchingSettings,
+  ) -> MatchBreakdown {
+    let occupants: Vec<&StudentProfile> = occupants
+      .iter()
+      .filter(|o| o.user_id != profile.user_id)
+      .collect();
+
+    let similarities: Vec<(Criterion, Option<f64>)> = Criterion::ALL
+      .iter()
+      .map(|&criterion| {
+        let values: Vec<f64> = occupants
+          .iter()
+          .filter_map(|o| criterion.similarity(profile, o))
+          .collect();
+        let similarity =
+          (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+        (criterion, similarity)
+      })
+      .collect();
+
+    let total_weight: f64 = similarities
+      .iter()
+      .filter(|(_, similarity)| similarity.is_some())
+      .map(|(criterion, _)| criterion.weight(settings))
+      .sum();
+
+    let criteria: Vec<CriterionScore> = similarities
+      .into_iter()
+      .map(|(criterion, similarity)| CriterionScore {
+        criterion,
+        weight: criterion.weight(settings),
+        similarity,
+        contribution: match similarity {
+          Some(similarity) if total_weight > 0.0 => {
+            criterion.weight(settings) * similarity / total_weight * 100.0
+          }
+          _ => 0.0,
+        },
+      })
+      .collect();
+
+    let score = if total_weight > 0.0 {
+      criteria.iter().map(|c| c.contribution).sum()
+    } else {
+      EMPTY_ROOM_SCORE
+    };
+
+    MatchBreakdown {
+      score,
+      occupants: occupants.len(),
+      criteria,
+    }
+  }
+
+  /// Whether a breakdown clears the settings' minimum score. Without anyone
+  /// to compare against the score is only the neutral `EMPTY_ROOM_SCORE`,
+  /// which the minimum does not apply to.
+  pub fn meets_minimum(breakdown: &MatchBreakdown, settings: &MatchingSettings) -> bool {
+    let compared = breakdown.criteria.iter().any(|c| c.similarity.is_some());
+    !compared || breakdown.score >= settings.min_score
+  }
+
+  /// Compatible rooms reaching the settings' minimum score, ordered from the
+  /// best to the worst match. Empty rooms are always kept.
+  pub fn rank_rooms<'a>(
+    profile: &StudentProfile,
+    rooms: &'a [Room],
+    occupants: &HashMap<Uuid, Vec<StudentProfile>>,
+    settings: &MatchingSettings,
+  ) -> Vec<RankedRoom<'a>> {
+    let mut ranked: Vec<RankedRoom<'a>> = rooms
+      .iter()
+      .filter(|room| Self::is_compatible(room, profile))
+      .filter_map(|room| {
+        let breakdown = Self::explain(
+          profile,
+          occupants.get(&room.id).map(Vec::as_slice).unwrap_or_default(),
+          settings,
+        );
+        Self::meets_minimum(&breakdown, settings).then_some(RankedRoom {
+          room,
+          score: breakdown.score,
+        })
+      })
+      .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+  }
+
+  /// Symmetric matrix of pairwise scores between `residents`, in the given
+  /// order; the diagonal is empty.
+  pub fn pairwise_scores(
+    residents: &[StudentProfile],
+    settings: &MatchingSettings,
+  ) -> Vec<Vec<Option<f64>>> {
+    let n = residents.len();
+    let mut matrix = vec![vec![None; n]; n];
+    for i in 0..n {
+      for j in i + 1..n {
+        let score = Self::score(&residents[i], std::slice::from_ref(&residents[j]), settings);
+        matrix[i][j] = Some(score);
+        matrix[j][i] = Some(score);
+      }
+    }
+    matrix
+  }
+
+  pub fn find_best_room<'a>(
+    profile: &StudentProfile,
+    rooms: &'a [Room],
+    occupants: &HashMap<Uuid, Vec<StudentProfile>>,
+    settings: &MatchingSettings,
+  ) -> Option<RankedRoom<'a>> {
+    Self::rank_rooms(profile, rooms, occupants, settings)
+      .into_iter()
+      .next()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sqlx::types::chrono::Utc;
+
+  fn settings(min_score: f64) -> MatchingSettings {
+    MatchingSettings {
+      version: 1,
+      wake_hours_weight: 3.0,
+      mbti_weight: 2.0,
+      hobbies_weight: 2.0,
+      age_weight: 1.0,
+      faculty_weight: 1.0,
+      course_weight: 1.0,
+      min_score,
+      is_active: true,
+      activated_at: None,
+      created_at: Utc::now(),
+    }
+  }
+
+  fn profile(
+    id: u128,
+    wake_hours: &str,
+    mbti: Option<&str>,
+    hobbies: &[&str],
+    age: i32,
+    course: i32,
+  ) -> StudentProfile {
+    StudentProfile {
+      user_id: Uuid::from_u128(id),
+      faculty: if id.is_multiple_of(2) {
+        "physics"
+      } else {
+        "history"
+      }
+      .to_string(),
+      course,
+      gender: "female".to_string(),
+      age,
+      wake_hours: wake_hours.to_string(),
+      hobbies: serde_json::json!(hobbies),
+      mbti: mbti.map(str::to_string),
+      updated_at: Utc::now(),
+    }
+  }
+
+  fn profiles() -> Vec<StudentProfile> {
+    vec![
+      profile(1, "early", Some("INTJ"), &["chess", "Running"], 18, 1),
+      profile(2, "late", Some("enfp"), &["running", "music"], 24, 3),
+      profile(3, "flexible", None, &[], 19, 2),
+      profile(4, "early", Some("estp"), &["music"], 35, 6),
+      profile(5, "late", Some("isfj"), &[" Chess "], 20, 1),
+    ]
+  }
+
+  fn room(id: u128, capacity: i32, current_occupants: i32) -> Room {
+    Room {
+      id: Uuid::from_u128(id),
+      number: format!("{}", id),
+      description: String::new(),
+      photo_url: None,
+      capacity,
+      current_occupants,
+      faculty_restriction: None,
+      course_restriction: None,
+      sex_restriction: "any".to_string(),
+      building: None,
+      status: RoomStatus::Available,
+    }
+  }
+
+  #[test]
+  fn mbti_table_is_symmetric() {
+    for (i, row) in MBTI_TABLE.iter().enumerate() {
+      for (j, &value) in row.iter().enumerate() {
+        assert_eq!(
+          value, MBTI_TABLE[j][i],
+          "{} / {}",
+          MBTI_TYPES[i], MBTI_TYPES[j]
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn similarity_stays_within_bounds() {
+    let profiles = profiles();
+    for a in &profiles {
+      for b in &profiles {
+        for criterion in Criterion::ALL {
+          if let Some(similarity) = criterion.similarity(a, b) {
+            assert!(
+              (0.0..=1.0).contains(&similarity),
+              "{:?}: {}",
+              criterion,
+              similarity
+            );
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn similarity_is_none_without_data() {
+    let profiles = profiles();
+    assert_eq!(Criterion::Mbti.similarity(&profiles[0], &profiles[2]), None);
+    assert_eq!(
+      Criterion::Hobbies.similarity(&profiles[2], &profiles[2]),
+      None
+    );
+    assert_eq!(
+      Criterion::Mbti.similarity(&profiles[0], &profiles[0]),
+      Some(0.75)
+    );
+    // Hobbies compare case- and whitespace-insensitively.
+    assert_eq!(
+      Criterion::Hobbies.similarity(&profiles[0], &profiles[4]),
+      Some(0.5)
+    );
+  }
+
+  #[test]
+  fn score_stays_between_0_and_100() {
+    let profiles = profiles();
+    let settings = settings(0.0);
+    for (i, student) in profiles.iter().enumerate() {
+      for end in i..=profiles.len() {
+        let score = MatchingService::score(student, &profiles[i..end], &settings);
+        assert!((0.0..=100.0).contains(&score), "{}", score);
+      }
+    }
+    let twin = StudentProfile {
+      user_id: Uuid::from_u128(99),
+      ..profiles[0].clone()
+    };
+    let score = MatchingService::score(&profiles[0], &[twin], &settings);
+    // Identical profiles only miss points on MBTI, where INTJ/INTJ is 3/4.
+    assert!((score - 95.0).abs() < 1e-9, "{}", score);
+  }
+
+  #[test]
+  fn empty_room_gets_the_neutral_score() {
+    let profiles = profiles();
+    let score = MatchingService::score(&profiles[0], &[], &settings(0.0));
+    assert_eq!(score, EMPTY_ROOM_SCORE);
+    // The student is never compared with themselves.
+    let score = MatchingService::score(&profiles[0], &profiles[..1], &settings(0.0));
+    assert_eq!(score, EMPTY_ROOM_SCORE);
+  }
+
+  #[test]
+  fn ranks_rooms_from_best_to_worst_match() {
+    let profiles = profiles();
+    let student = &profiles[0];
+    let rooms = vec![
+      room(10, 2, 1),
+      room(11, 2, 1),
+      room(12, 2, 0),
+      room(13, 2, 2),
+    ];
+    let twin = StudentProfile {
+      user_id: Uuid::from_u128(99),
+      ..student.clone()
+    };
+    let occupants = HashMap::from([
+      (rooms[0].id, vec![profiles[3].clone()]),
+      (rooms[1].id, vec![twin]),
+      (rooms[3].id, vec![profiles[1].clone(), profiles[2].clone()]),
+    ]);
+
+    let ranked = MatchingService::rank_rooms(student, &rooms, &occupants, &settings(0.0));
+    let order: Vec<Uuid> = ranked.iter().map(|ranked| ranked.room.id).collect();
+    // A close match beats the empty room, which beats a poor match; the
+    // full room is not compatible at all.
+    assert_eq!(order, vec![rooms[1].id, rooms[2].id, rooms[0].id]);
+    assert!(ranked.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    assert_eq!(
+      MatchingService::find_best_room(student, &rooms, &occupants, &settings(0.0))
+        .map(|best| best.room.id),
+      Some(rooms[1].id)
+    );
+  }
+
+  #[test]
+  fn contributions_add_up_to_the_score() {
+    let profiles = profiles();
+    let settings = settings(0.0);
+    for student in &profiles {
+      let others: Vec<StudentProfile> = profiles
+        .iter()
+        .filter(|other| other.user_id != student.user_id)
+        .cloned()
+        .collect();
+      for end in 1..=others.len() {
+        let breakdown = MatchingService::explain(student, &others[..end], &settings);
+        let sum: f64 = breakdown.criteria.iter().map(|c| c.contribution).sum();
+        assert!(
+          (sum - breakdown.score).abs() < 1e-9,
+          "{} vs {}",
+          sum,
+          breakdown.score
+        );
+        assert_eq!(breakdown.occupants, end);
+      }
+    }
+  }
+
+  #[test]
+  fn unevaluated_criterion_contributes_nothing() {
+    // Neither student gave an MBTI type or any hobbies.
+    let student = profiles()[2].clone();
+    let twin = StudentProfile {
+      user_id: Uuid::from_u128(99),
+      ..student.clone()
+    };
+    let breakdown = MatchingService::explain(&student, &[twin], &settings(0.0));
+    let skipped: Vec<Criterion> = breakdown
+      .criteria
+      .iter()
+      .filter(|c| c.similarity.is_none())
+      .map(|c| c.criterion)
+      .collect();
+    assert_eq!(skipped, vec![Criterion::Mbti, Criterion::Hobbies]);
+    for criterion in &breakdown.criteria {
+      if criterion.similarity.is_none() {
+        assert_eq!(criterion.contribution, 0.0, "{:?}", criterion.criterion);
+      }
+    }
+    // The weights left are renormalised, so a perfect fit on them is 100.
+    assert!(
+      (breakdown.score - 100.0).abs() < 1e-9,
+      "{}",
+      breakdown.score
+    );
+  }
+
+  #[test]
+  fn minimum_score_keeps_empty_rooms() {
+    let profiles = profiles();
+    let student = &profiles[0];
+    let rooms = vec![room(10, 2, 1), room(11, 2, 0), room(12, 3, 1)];
+    let twin = StudentProfile {
+      user_id: Uuid::from_u128(99),
+      ..student.clone()
+    };
+    let occupants = HashMap::from([
+      (rooms[0].id, vec![profiles[3].clone()]),
+      (rooms[2].id, vec![twin]),
+    ]);
+
+    // The bar is above the empty room's neutral score, yet only the poor
+    // match in the first room is dropped.
+    let ranked = MatchingService::rank_rooms(student, &rooms, &occupants, &settings(60.0));
+    let order: Vec<Uuid> = ranked.iter().map(|ranked| ranked.room.id).collect();
+    assert_eq!(order, vec![rooms[2].id, rooms[1].id]);
+    assert_eq!(ranked[1].score, EMPTY_ROOM_SCORE);
+
+    let ranked =
+      MatchingService::rank_rooms(student, &rooms[..2], &HashMap::new(), &settings(100.0));
+    assert_eq!(ranked.len(), 2);
   }
 }
@@ -1 +1,6 @@
+pub mod allocation;
+pub mod assignment;
+pub mod deferred_acceptance;
+pub mod lottery;
 pub mod matching;
+pub mod stable_roommates;
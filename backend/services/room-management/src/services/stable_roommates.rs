@@ -0,0 +1,231 @@
+/// Irving's stable roommates algorithm for incomplete preference lists.
+///
+/// `prefs[i]` lists the agents `i` finds acceptable, best first.
+/// Acceptability must be mutual. Returns each agent's partner, or `None`
+/// when the instance admits no stable matching.
+pub fn stable_roommates(prefs: &[Vec<usize>]) -> Option<Vec<Option<usize>>> {
+  let mut table = Table::new(prefs);
+  table.phase_one();
+  if !table.phase_two() {
+    return None;
+  }
+
+  let matching: Vec<Option<usize>> = (0..prefs.len()).map(|i| table.first(i)).collect();
+  let consistent = matching
+    .iter()
+    .enumerate()
+    .all(|(i, partner)| partner.is_none_or(|p| matching[p] == Some(i)));
+  consistent.then_some(matching)
+}
+
+/// Pairs of agents that both strictly prefer each other to their partners in
+/// `matching` (being single is worse than any acceptable partner).
+pub fn blocking_pairs(prefs: &[Vec<usize>], matching: &[Option<usize>]) -> Vec<(usize, usize)> {
+  let rank = ranks(prefs);
+  let prefers = |a: usize, b: usize| match matching[a] {
+    Some(current) => rank[a][b] < rank[a][current],
+    None => true,
+  };
+
+  let mut pairs = Vec::new();
+  for (a, list) in prefs.iter().enumerate() {
+    for &b in list {
+      if a < b && matching[a] != Some(b) && prefers(a, b) && prefers(b, a) {
+        pairs.push((a, b));
+      }
+    }
+  }
+  pairs
+}
+
+/// Fallback when no stable matching exists: takes `pairs` in the given order
+/// (best first) and keeps every pair whose agents are both still single.
+pub fn greedy_matching(agents: usize, pairs: &[(usize, usize)]) -> Vec<Option<usize>> {
+  let mut matching = vec![None; agents];
+  for &(a, b) in pairs {
+    if matching[a].is_none() && matching[b].is_none() {
+      matching[a] = Some(b);
+      matching[b] = Some(a);
+    }
+  }
+  matching
+}
+
+fn ranks(prefs: &[Vec<usize>]) -> Vec<Vec<usize>> {
+  let n = prefs.len();
+  let mut rank = vec![vec![usize::MAX; n]; n];
+  for (i, list) in prefs.iter().enumerate() {
+    for (position, &j) in list.iter().enumerate() {
+      rank[i][j] = position;
+    }
+  }
+  rank
+}
+
+/// Preference table with symmetric deletions.
+struct Table<'a> {
+  prefs: &'a [Vec<usize>],
+  alive: Vec<Vec<bool>>,
+}
+
+impl<'a> Table<'a> {
+  fn new(prefs: &'a [Vec<usize>]) -> Self {
+    let n = prefs.len();
+    let mut alive = vec![vec![false; n]; n];
+    for (i, list) in prefs.iter().enumerate() {
+      for &j in list {
+        alive[i][j] = true;
+      }
+    }
+    Table { prefs, alive }
+  }
+
+  fn delete(&mut self, a: usize, b: usize) {
+    self.alive[a][b] = false;
+    self.alive[b][a] = false;
+  }
+
+  fn list(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+    self.prefs[i].iter().copied().filter(move |&j| self.alive[i][j])
+  }
+
+  fn first(&self, i: usize) -> Option<usize> {
+    self.list(i).next()
+  }
+
+  fn second(&self, i: usize) -> Option<usize> {
+    self.list(i).nth(1)
+  }
+
+  fn last(&self, i: usize) -> Option<usize> {
+    self.list(i).last()
+  }
+
+  /// Everyone after `x` in `y`'s list.
+  fn successors(&self, y: usize, x: usize) -> Vec<usize> {
+    self.list(y).skip_while(|&w| w != x).skip(1).collect()
+  }
+
+  /// Proposal phase: every agent proposes down its list and each recipient
+  /// keeps only its best proposal, cutting everyone worse from its list.
+  fn phase_one(&mut self) {
+    let n = self.prefs.len();
+    let mut held: Vec<Option<usize>> = vec![None; n];
+    let mut free: Vec<usize> = (0..n).rev().collect();
+
+    while let Some(x) = free.pop() {
+      let Some(y) = self.first(x) else { continue };
+      if let Some(previous) = held[y].replace(x) {
+        free.push(previous);
+      }
+      for w in self.successors(y, x) {
+        self.delete(y, w);
+      }
+    }
+
+    // Nobody proposed to these agents, so they stay single in every stable
+    // matching; dropping them keeps the table consistent for phase two.
+    for (y, holds) in held.iter().enumerate() {
+      if holds.is_none() {
+        for w in self.list(y).collect::<Vec<_>>() {
+          self.delete(y, w);
+        }
+      }
+    }
+  }
+
+  /// Rotation elimination. Returns `false` if some list runs empty, which
+  /// means no stable matching exists.
+  fn phase_two(&mut self) -> bool {
+    let n = self.prefs.len();
+    let active: Vec<bool> = (0..n).map(|i| self.first(i).is_some()).collect();
+    while let Some(start) = (0..n).find(|&i| self.second(i).is_some()) {
+      let mut sequence = vec![start];
+      let cycle_start = loop {
+        let x = *sequence.last().unwrap();
+        let Some(next) = self.second(x).and_then(|y| self.last(y)) else {
+          return false;
+        };
+        if let Some(position) = sequence.iter().position(|&s| s == next) {
+          break position;
+        }
+        sequence.push(next);
+      };
+
+      let rotation: Vec<(usize, usize)> = sequence[cycle_start..]
+        .iter()
+        .filter_map(|&x| self.second(x).map(|y| (x, y)))
+        .collect();
+      for (x, y) in rotation {
+        for w in self.successors(y, x) {
+          self.delete(y, w);
+        }
+      }
+
+      let emptied = (0..n).any(|i| active[i] && self.first(i).is_none());
+      if emptied {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Irving's six-person example, renumbered from zero.
+  fn irving_example() -> Vec<Vec<usize>> {
+    vec![
+      vec![2, 3, 1, 5, 4],
+      vec![5, 4, 3, 0, 2],
+      vec![1, 3, 4, 0, 5],
+      vec![4, 1, 2, 5, 0],
+      vec![2, 0, 1, 3, 5],
+      vec![4, 0, 2, 3, 1],
+    ]
+  }
+
+  #[test]
+  fn finds_stable_matching() {
+    let prefs = irving_example();
+    let matching = stable_roommates(&prefs).expect("instance is solvable");
+    assert_eq!(
+      matching,
+      vec![Some(5), Some(3), Some(4), Some(1), Some(2), Some(0)]
+    );
+    assert!(blocking_pairs(&prefs, &matching).is_empty());
+  }
+
+  #[test]
+  fn unsolvable_instance_falls_back_to_greedy() {
+    // 0, 1 and 2 each put the next one first; 3 is everyone's last choice,
+    // so whoever gets 3 is blocked with someone who prefers them.
+    let prefs = vec![
+      vec![1, 2, 3],
+      vec![2, 0, 3],
+      vec![0, 1, 3],
+      vec![0, 1, 2],
+    ];
+    assert_eq!(stable_roommates(&prefs), None);
+
+    let matching = greedy_matching(4, &[(0, 1), (1, 2), (0, 2), (0, 3), (1, 3), (2, 3)]);
+    assert_eq!(matching, vec![Some(1), Some(0), Some(3), Some(2)]);
+    assert_eq!(blocking_pairs(&prefs, &matching), vec![(1, 2)]);
+  }
+
+  #[test]
+  fn odd_number_of_agents_leaves_one_single() {
+    let prefs = vec![vec![1, 2], vec![0, 2], vec![0, 1]];
+    let matching = stable_roommates(&prefs).expect("instance is solvable");
+    assert_eq!(matching, vec![Some(1), Some(0), None]);
+    assert!(blocking_pairs(&prefs, &matching).is_empty());
+  }
+
+  #[test]
+  fn odd_cycle_has_no_stable_matching() {
+    let prefs = vec![vec![1, 2], vec![2, 0], vec![0, 1]];
+    assert_eq!(stable_roommates(&prefs), None);
+  }
+}